@@ -0,0 +1,159 @@
+use bevy::asset::LoadState;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+use crate::pigs::{CantAffordFeedback, CantAffordPig, PigSoldEvent};
+
+// All of the game's sounds are handled in here.
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicVolume>()
+            .register_type::<MusicVolume>()
+            .add_systems(Startup, (load_sounds, start_music).chain())
+            .add_systems(
+                Update,
+                (
+                    play_cant_afford_sound,
+                    play_sale_sound,
+                    apply_music_volume,
+                    warn_missing_sounds,
+                ),
+            );
+    }
+}
+
+// Handles are cheap to clone, so we load each sound once at startup and keep
+// the handle around rather than asking the asset server every time.
+#[derive(Resource)]
+pub struct Sounds {
+    pub error: Handle<AudioSource>,
+    pub sale: Handle<AudioSource>,
+    pub music: Handle<AudioSource>,
+}
+
+// How loud the background music is, from 0 (silent) to 1 (full volume).
+#[derive(Resource, Reflect, InspectorOptions)]
+#[reflect(Resource, InspectorOptions)]
+pub struct MusicVolume(#[inspector(min = 0.0, max = 1.0)] pub f32);
+
+impl Default for MusicVolume {
+    fn default() -> Self {
+        MusicVolume(0.5)
+    }
+}
+
+// Tag component for the entity playing the background music, so we can find
+// it again to change its volume.
+#[derive(Component)]
+pub struct BackgroundMusic;
+
+// The error sound won't play more often than this, so mashing the spawn key
+// while broke doesn't machine-gun it.
+const ERROR_SOUND_COOLDOWN: f32 = 0.5;
+
+// Out of the box bevy only plays .ogg files, our sounds are .wav so the "wav"
+// feature is turned on for bevy in Cargo.toml.
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        error: asset_server.load("sounds/error.wav"),
+        sale: asset_server.load("sounds/cha-ching.wav"),
+        music: asset_server.load("sounds/music.wav"),
+    });
+}
+
+// LOOP starts the track again from the beginning every time it ends.
+fn start_music(mut commands: Commands, sounds: Res<Sounds>, volume: Res<MusicVolume>) {
+    commands.spawn((
+        AudioBundle {
+            source: sounds.music.clone(),
+            settings: PlaybackSettings {
+                volume: Volume::new_relative(volume.0),
+                ..PlaybackSettings::LOOP
+            },
+        },
+        BackgroundMusic,
+        Name::new("Background music"),
+    ));
+}
+
+// Bevy adds an AudioSink to the music once it starts playing, that is what we
+// talk to if the volume is changed while the game is running.
+fn apply_music_volume(volume: Res<MusicVolume>, music: Query<&AudioSink, With<BackgroundMusic>>) {
+    if !volume.is_changed() {
+        return;
+    }
+    for sink in &music {
+        sink.set_volume(volume.0.max(0.0));
+    }
+}
+
+// A missing sound file doesn't stop the game, the sound just never plays. We
+// warn about each one once so it's obvious why it's quiet.
+fn warn_missing_sounds(
+    sounds: Res<Sounds>,
+    asset_server: Res<AssetServer>,
+    // The names of the sounds we've already warned about.
+    mut warned: Local<Vec<&'static str>>,
+) {
+    let handles = [
+        ("error", &sounds.error),
+        ("sale", &sounds.sale),
+        ("music", &sounds.music),
+    ];
+    for (name, handle) in handles {
+        if !warned.contains(&name)
+            && asset_server.get_load_state(handle) == Some(LoadState::Failed)
+        {
+            warn!("Couldn't load the {name} sound, carrying on without it");
+            warned.push(name);
+        }
+    }
+}
+
+fn play_cant_afford_sound(
+    mut commands: Commands,
+    mut cant_afford: EventReader<CantAffordPig>,
+    feedback: Res<CantAffordFeedback>,
+    sounds: Res<Sounds>,
+    time: Res<Time>,
+    // Local is a bit of state that belongs to just this system and sticks
+    // around between runs, a good fit for remembering when we last played.
+    mut last_played: Local<Option<f32>>,
+) {
+    if cant_afford.read().count() == 0 || !feedback.sound {
+        return;
+    }
+
+    let now = time.elapsed_seconds();
+    if last_played.is_some_and(|last| now - last < ERROR_SOUND_COOLDOWN) {
+        return;
+    }
+    *last_played = Some(now);
+
+    // DESPAWN gets rid of the entity once the sound has finished so one shot
+    // sounds don't pile up.
+    commands.spawn(AudioBundle {
+        source: sounds.error.clone(),
+        settings: PlaybackSettings::DESPAWN,
+    });
+}
+
+// A cha-ching every time we make some money. When lots of pigs sell in the
+// same frame they'd all play on top of each other and just sound like one
+// loud one, so we only play the sound once per frame however many sold.
+fn play_sale_sound(
+    mut commands: Commands,
+    mut sold: EventReader<PigSoldEvent>,
+    sounds: Res<Sounds>,
+) {
+    if sold.read().count() == 0 {
+        return;
+    }
+
+    commands.spawn(AudioBundle {
+        source: sounds.sale.clone(),
+        settings: PlaybackSettings::DESPAWN,
+    });
+}
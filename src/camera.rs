@@ -0,0 +1,224 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::window::PrimaryWindow;
+use crate::Player;
+
+// Everything to do with how the camera looks at the world lives in here.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .init_resource::<GameViewport>()
+            .register_type::<CameraSettings>()
+            .add_systems(Startup, spawn_letterbox_bars)
+            .add_systems(Update, (letterbox, camera_follow, camera_zoom));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CameraSettings {
+    // When this is set the game is drawn at this width / height ratio and
+    // anything left over in the window is filled with black bars. None means
+    // the game simply fills the whole window.
+    pub aspect_ratio: Option<f32>,
+    // The camera's zoom when the game starts. Above 1 shows more of the world
+    // and below 1 zooms in.
+    pub initial_scale: f32,
+    // How far the camera is allowed to zoom in and out.
+    pub min_scale: f32,
+    pub max_scale: f32,
+    // How quickly the camera catches up with the player. Higher is snappier.
+    pub follow_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            aspect_ratio: None,
+            initial_scale: 1.0,
+            min_scale: 0.25,
+            max_scale: 4.0,
+            follow_speed: 5.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    // Not f32::clamp, that panics when min_scale is bigger than max_scale and
+    // both of them can be typed into the inspector.
+    pub fn clamp_scale(&self, scale: f32) -> f32 {
+        scale.max(self.min_scale).min(self.max_scale)
+    }
+}
+
+// The part of the window, in logical pixels, that the game is drawn into. This
+// is None when we aren't letterboxing and the game uses the whole window. The
+// UI reads this so it can sit inside the game area rather than on the bars.
+#[derive(Resource, Default, PartialEq)]
+pub struct GameViewport(pub Option<Rect>);
+
+// Tag component for the two black bars either side of the game.
+#[derive(Component)]
+pub struct LetterboxBar;
+
+// The UI isn't clipped by the camera's viewport, so rather than relying on the
+// clear colour we cover the unused parts of the window with plain black UI
+// nodes. They start hidden and letterbox moves them into place.
+fn spawn_letterbox_bars(mut commands: Commands) {
+    for _ in 0..2 {
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                visibility: Visibility::Hidden,
+                // This makes sure the bars are drawn on top of the rest of the
+                // UI.
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            LetterboxBar,
+            Name::new("Letterbox bar"),
+        ));
+    }
+}
+
+fn letterbox(
+    settings: Res<CameraSettings>,
+    mut viewport: ResMut<GameViewport>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<Camera2d>>,
+    mut bars: Query<(&mut Style, &mut Visibility), With<LetterboxBar>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    let game_rect = settings
+        .aspect_ratio
+        .filter(|ratio| *ratio > 0.0)
+        .map(|ratio| fit_aspect(window_size, ratio));
+
+    // Only touch things when the rect actually changes, writing to Camera or
+    // Style every frame would make bevy redo work it doesn't need to.
+    if viewport.0 == game_rect {
+        return;
+    }
+    viewport.0 = game_rect;
+
+    let scale = window.scale_factor() as f32;
+    for mut camera in &mut cameras {
+        camera.viewport = game_rect.map(|rect| Viewport {
+            physical_position: (rect.min * scale).as_uvec2(),
+            physical_size: (rect.size() * scale).as_uvec2().max(UVec2::ONE),
+            ..default()
+        });
+    }
+
+    let bar_rects = match game_rect {
+        Some(rect) => bar_rects(window_size, rect),
+        None => [Rect::default(), Rect::default()],
+    };
+    for ((mut style, mut visibility), bar) in bars.iter_mut().zip(bar_rects) {
+        style.left = Val::Px(bar.min.x);
+        style.top = Val::Px(bar.min.y);
+        style.width = Val::Px(bar.width());
+        style.height = Val::Px(bar.height());
+        *visibility = if game_rect.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// Moves the camera part of the way towards the player each frame, which gives
+// a nice smooth follow rather than the camera being glued to the player. The
+// Without filters tell bevy the two queries never touch the same Transform.
+fn camera_follow(
+    settings: Res<CameraSettings>,
+    player: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut cameras: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    time: Res<Time>,
+) {
+    // get_single lets us quietly do nothing if the player hasn't been spawned
+    // yet instead of panicking.
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let step = (settings.follow_speed * time.delta_seconds()).min(1.0);
+
+    for mut camera in &mut cameras {
+        let target = player.translation.truncate();
+        let position = camera.translation.truncate().lerp(target, step);
+        // Only x and y move, the camera's z has to stay where it is or sprites
+        // would end up behind it.
+        camera.translation.x = position.x;
+        camera.translation.y = position.y;
+    }
+}
+
+// How much one notch of the scroll wheel zooms by.
+const ZOOM_SENSITIVITY: f32 = 0.1;
+// Touchpads scroll in pixels rather than notches, roughly this many pixels make
+// up one notch.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+fn camera_zoom(
+    settings: Res<CameraSettings>,
+    mut scroll: EventReader<MouseWheel>,
+    mut projections: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    let notches: f32 = scroll
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if notches == 0.0 {
+        return;
+    }
+
+    // Scaling by exp rather than adding a fixed amount means each notch zooms
+    // by the same proportion, so zooming feels the same at every level.
+    // Scrolling up makes the scale smaller, which zooms in.
+    let factor = (-notches * ZOOM_SENSITIVITY).exp();
+    for mut projection in &mut projections {
+        projection.scale = settings.clamp_scale(projection.scale * factor);
+    }
+}
+
+// Works out the biggest rect with the given aspect ratio that fits in the
+// window, centred in it.
+fn fit_aspect(window_size: Vec2, ratio: f32) -> Rect {
+    let size = if window_size.x / window_size.y > ratio {
+        // The window is too wide so we get bars down the sides.
+        Vec2::new(window_size.y * ratio, window_size.y)
+    } else {
+        // The window is too tall so we get bars on the top and bottom.
+        Vec2::new(window_size.x, window_size.x / ratio)
+    };
+    Rect::from_center_size(window_size / 2.0, size)
+}
+
+// The two rects either side of the game area that need covering up.
+fn bar_rects(window_size: Vec2, game: Rect) -> [Rect; 2] {
+    if game.width() < window_size.x {
+        [
+            Rect::new(0.0, 0.0, game.min.x, window_size.y),
+            Rect::new(game.max.x, 0.0, window_size.x, window_size.y),
+        ]
+    } else {
+        [
+            Rect::new(0.0, 0.0, window_size.x, game.min.y),
+            Rect::new(0.0, game.max.y, window_size.x, window_size.y),
+        ]
+    }
+}
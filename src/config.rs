@@ -0,0 +1,73 @@
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Numbers for balancing the game that can be changed without recompiling. They
+// are read from config.ron next to the game when it starts.
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        // PreStartup runs before Startup, so the config is already in place by
+        // the time setup spawns the player.
+        app.register_type::<GameConfig>()
+            .add_systems(PreStartup, load_config);
+    }
+}
+
+const CONFIG_PATH: &str = "config.ron";
+
+// serde(default) means any field missing from the file keeps its default, so
+// the config only needs to list the things you want to change. A file like
+// (pig_price: 20.0) is enough to make pigs dearer.
+#[derive(Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GameConfig {
+    // What the first pig costs, the price goes up from here as the herd grows.
+    pub pig_price: f32,
+    // What a newborn pig is worth, it grows in value from here as it ages.
+    pub pig_payout: f32,
+    pub player_speed: f32,
+    // The shortest and longest time, in seconds, that a pig can live for.
+    pub pig_lifetime_range: (f32, f32),
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            pig_price: 10.0,
+            pig_payout: 10.0,
+            player_speed: 100.0,
+            pig_lifetime_range: (1.0, 5.0),
+        }
+    }
+}
+
+impl GameConfig {
+    // Picking from a range panics if it is backwards, and a timer can't have a
+    // negative length, so we tidy up whatever the file gave us first.
+    pub fn lifetime_range(&self) -> RangeInclusive<f32> {
+        let (a, b) = self.pig_lifetime_range;
+        let (min, max) = (a.min(b).max(0.0), a.max(b).max(0.0));
+        min..=max
+    }
+}
+
+// A missing config file is fine, we just use the defaults. A broken one gets a
+// warning and the defaults too, rather than stopping the game from starting.
+fn load_config(mut commands: Commands) {
+    let config = match std::fs::read_to_string(CONFIG_PATH) {
+        Ok(text) => ron::from_str::<GameConfig>(&text).unwrap_or_else(|error| {
+            warn!("{CONFIG_PATH} is malformed, using the default config: {error}");
+            GameConfig::default()
+        }),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => GameConfig::default(),
+        Err(error) => {
+            warn!("Couldn't read {CONFIG_PATH}, using the default config: {error}");
+            GameConfig::default()
+        }
+    };
+    commands.insert_resource(config);
+}
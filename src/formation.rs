@@ -0,0 +1,151 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use crate::pigs::{MoveTarget, Pig};
+use crate::{GameState, Player};
+
+// Pressing G lines every pig up in a neat shape around the player. Each press
+// uses the next shape in the list, and after a little while the pigs are let
+// go again.
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Formation>()
+            .register_type::<Formation>()
+            .add_systems(
+                Update,
+                (arrange_formation, release_formation).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Reflect)]
+pub enum FormationShape {
+    #[default]
+    Grid,
+    Circle,
+}
+
+impl FormationShape {
+    fn next(self) -> Self {
+        match self {
+            FormationShape::Grid => FormationShape::Circle,
+            FormationShape::Circle => FormationShape::Grid,
+        }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Formation {
+    // The shape the next key press will use.
+    pub next_shape: FormationShape,
+    // How far apart neighbouring pigs stand.
+    pub spacing: f32,
+    // How many seconds the pigs hold their places before being let go.
+    pub hold_time: f32,
+    // Counts down while a formation is being held.
+    pub hold: Option<Timer>,
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Formation {
+            next_shape: FormationShape::Grid,
+            spacing: 24.0,
+            hold_time: 5.0,
+            hold: None,
+        }
+    }
+}
+
+// Marks the pigs that were put into the current formation, so that when we let
+// them go we only clear the targets we set ourselves.
+#[derive(Component)]
+pub struct InFormation;
+
+fn arrange_formation(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut formation: ResMut<Formation>,
+    player: Query<&Transform, With<Player>>,
+    mut pigs: Query<(Entity, &mut MoveTarget), With<Pig>>,
+) {
+    if !input.just_pressed(KeyCode::G) {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let centre = player.translation.truncate();
+
+    // Sorting by entity means each pig keeps roughly the same slot if the key
+    // is pressed again, rather than everyone swapping places.
+    let mut pigs: Vec<_> = pigs.iter_mut().collect();
+    pigs.sort_by_key(|(entity, _)| *entity);
+
+    let shape = formation.next_shape;
+    let slots = formation_slots(shape, pigs.len(), formation.spacing);
+    for ((entity, mut target), slot) in pigs.into_iter().zip(slots) {
+        target.0 = Some(centre + slot);
+        // The pig could be sold or reset away later this frame, before this
+        // command is applied. insert would panic if it's gone by then,
+        // try_insert just skips it.
+        commands.entity(entity).try_insert(InFormation);
+    }
+
+    formation.next_shape = shape.next();
+    // hold_time can be set to anything in the inspector, and a timer can't
+    // count down from a negative number of seconds.
+    formation.hold = Some(Timer::from_seconds(formation.hold_time.max(0.0), TimerMode::Once));
+}
+
+fn release_formation(
+    mut commands: Commands,
+    mut formation: ResMut<Formation>,
+    mut pigs: Query<(Entity, &mut MoveTarget), With<InFormation>>,
+    time: Res<Time>,
+) {
+    let Some(hold) = formation.hold.as_mut() else {
+        return;
+    };
+    if !hold.tick(time.delta()).finished() {
+        return;
+    }
+
+    formation.hold = None;
+    for (entity, mut target) in &mut pigs {
+        target.0 = None;
+        commands.entity(entity).remove::<InFormation>();
+    }
+}
+
+// Works out where each of count pigs should stand, relative to the player.
+fn formation_slots(shape: FormationShape, count: usize, spacing: f32) -> Vec<Vec2> {
+    match shape {
+        FormationShape::Grid => {
+            // As close to a square as we can get, centred on the player.
+            let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+            let rows = count.div_ceil(columns);
+            let offset = Vec2::new(columns as f32 - 1.0, rows as f32 - 1.0) * spacing / 2.0;
+            (0..count)
+                .map(|i| {
+                    let cell = Vec2::new((i % columns) as f32, (i / columns) as f32);
+                    cell * spacing - offset
+                })
+                .collect()
+        }
+        FormationShape::Circle => {
+            // The circle gets bigger as more pigs join so they stay spacing
+            // apart around the edge.
+            let radius = (count as f32 * spacing / TAU).max(spacing);
+            (0..count)
+                .map(|i| {
+                    let angle = i as f32 / count as f32 * TAU;
+                    Vec2::new(angle.cos(), angle.sin()) * radius
+                })
+                .collect()
+        }
+    }
+}
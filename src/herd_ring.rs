@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use crate::pigs::Pig;
+use crate::Player;
+
+// This plugin draws a ring around the player that gets bigger the more pigs
+// are hanging around nearby. It is a quick way to get a feel for how crowded
+// things are without counting pigs.
+pub struct HerdRingPlugin;
+
+impl Plugin for HerdRingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HerdRing>()
+            .register_type::<HerdRing>()
+            .add_systems(Update, (toggle_herd_ring, draw_herd_ring));
+    }
+}
+
+// Everything about how the ring behaves lives here. Deriving Reflect and
+// registering the type means all of these show up in the inspector so they
+// can be tweaked while the game is running.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct HerdRing {
+    pub visible: bool,
+    // Only pigs within this distance of the player are counted.
+    pub count_radius: f32,
+    // The size of the ring with no pigs around, and how much each nearby pig
+    // adds to it.
+    pub base_radius: f32,
+    pub radius_per_pig: f32,
+    // The ring never grows past this so it can't wander off the screen.
+    pub max_radius: f32,
+    // How quickly the drawn ring catches up with its target size.
+    pub smoothing: f32,
+    // The radius we actually drew last frame. We ease this towards the target
+    // rather than snapping so the ring doesn't jitter as pigs come and go.
+    pub current_radius: f32,
+}
+
+impl Default for HerdRing {
+    fn default() -> Self {
+        HerdRing {
+            visible: false,
+            count_radius: 150.0,
+            base_radius: 16.0,
+            radius_per_pig: 4.0,
+            max_radius: 200.0,
+            smoothing: 5.0,
+            current_radius: 16.0,
+        }
+    }
+}
+
+fn toggle_herd_ring(input: Res<Input<KeyCode>>, mut ring: ResMut<HerdRing>) {
+    if input.just_pressed(KeyCode::H) {
+        ring.visible = !ring.visible;
+    }
+}
+
+// Gizmos are immediate mode, which means they only last a single frame and
+// have to be drawn again every frame. That suits us since the ring is always
+// changing anyway.
+fn draw_herd_ring(
+    mut gizmos: Gizmos,
+    mut ring: ResMut<HerdRing>,
+    player: Query<&GlobalTransform, With<Player>>,
+    // Pigs are children of the pig parent so their Transform is relative to
+    // it, GlobalTransform gives us their actual position in the world.
+    pigs: Query<&GlobalTransform, With<Pig>>,
+    time: Res<Time>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let centre = player.translation().truncate();
+
+    let nearby = pigs
+        .iter()
+        .filter(|pig| pig.translation().truncate().distance(centre) <= ring.count_radius)
+        .count();
+
+    let target = (ring.base_radius + ring.radius_per_pig * nearby as f32)
+        .min(ring.max_radius);
+    let step = (ring.smoothing * time.delta_seconds()).min(1.0);
+    ring.current_radius += (target - ring.current_radius) * step;
+
+    if ring.visible {
+        gizmos.circle_2d(centre, ring.current_radius, Color::GREEN);
+    }
+}
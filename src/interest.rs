@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use crate::settings::Currency;
+use crate::{GameState, Money};
+
+// Our money slowly grows by itself, like it's sat in a bank account.
+pub struct InterestPlugin;
+
+impl Plugin for InterestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InterestSettings>()
+            .init_resource::<InterestTimer>()
+            .register_type::<InterestSettings>()
+            // Interest is part of the economy, so like the pigs it ticks in
+            // FixedUpdate and pays out the same whatever the frame rate.
+            .add_systems(FixedUpdate, money_interest.run_if(in_state(GameState::Playing)));
+    }
+}
+
+// Every interval seconds we're paid rate times our current money, so a rate of
+// 0.01 is 1% interest.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct InterestSettings {
+    pub rate: f32,
+    pub interval: f32,
+}
+
+impl Default for InterestSettings {
+    fn default() -> Self {
+        InterestSettings {
+            rate: 0.01,
+            interval: 10.0,
+        }
+    }
+}
+
+// Keeps track of how long it has been since we were last paid interest.
+#[derive(Resource)]
+pub struct InterestTimer(pub Timer);
+
+impl Default for InterestTimer {
+    fn default() -> Self {
+        InterestTimer(Timer::from_seconds(
+            InterestSettings::default().interval,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+fn money_interest(
+    settings: Res<InterestSettings>,
+    mut timer: ResMut<InterestTimer>,
+    mut money: ResMut<Money>,
+    currency: Res<Currency>,
+    time: Res<Time<Fixed>>,
+) {
+    // If the interval has been changed in the inspector the timer needs to
+    // know about it.
+    let interval = Duration::from_secs_f32(settings.interval.max(0.1));
+    if timer.0.duration() != interval {
+        timer.0.set_duration(interval);
+    }
+
+    timer.0.tick(time.delta());
+    // A repeating timer can go off more than once in a single long frame, so
+    // we pay out once for every time it did.
+    for _ in 0..timer.0.times_finished_this_tick() {
+        let interest = money.0 * settings.rate;
+        money.0 += interest;
+        info!(
+            "Earned {} in interest! Current money: {}",
+            currency.format(interest),
+            currency.format(money.0)
+        );
+    }
+}
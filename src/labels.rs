@@ -0,0 +1,251 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use crate::pigs::{Pig, PigSoldEvent};
+use crate::settings::Currency;
+
+// This plugin handles the little bits of text that float around our pigs.
+pub struct PigLabelPlugin;
+
+impl Plugin for PigLabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowValueLabels>()
+            .init_resource::<ShowAgeLabels>()
+            .add_systems(
+                Update,
+                (
+                    toggle_value_labels,
+                    update_value_labels,
+                    toggle_age_labels,
+                    update_age_labels,
+                    keep_labels_full_size,
+                    spawn_sale_popups,
+                    float_text,
+                ),
+            );
+    }
+}
+
+// Tag component for every label hanging off a pig, whatever it shows.
+#[derive(Component)]
+pub struct PigLabel;
+
+// Tag component for the text that shows how much a pig will sell for.
+#[derive(Component)]
+pub struct PigValueLabel;
+
+// Whether or not the value labels should be drawn. With a lot of pigs on the
+// screen the labels get pretty cluttered so we let the player turn them off.
+#[derive(Resource)]
+pub struct ShowValueLabels(pub bool);
+
+impl Default for ShowValueLabels {
+    fn default() -> Self {
+        ShowValueLabels(true)
+    }
+}
+
+// Tag component for the debug text showing how long a pig has left to live.
+#[derive(Component)]
+pub struct PigAgeLabel;
+
+// The age labels are a debugging aid for checking the lifetime timers, so they
+// start hidden and F2 shows them.
+#[derive(Resource, Default)]
+pub struct ShowAgeLabels(pub bool);
+
+// How far above the middle of the pig the label sits. The pig sprite is 16
+// pixels tall so this puts the text just above its head. The age label uses
+// the same distance below the pig so the two never overlap.
+const LABEL_OFFSET: f32 = 14.0;
+
+// Everything needed to give a new pig its labels. spawn_pig uses this to
+// spawn the labels in the same breath as the pig itself. Adding them later
+// from a separate system isn't safe, as a pig can be sold or reset away on the
+// very frame it first shows up, and adding children to a pig that no longer
+// exists panics.
+#[derive(SystemParam)]
+pub struct PigLabelSpawner<'w> {
+    show_values: Res<'w, ShowValueLabels>,
+    show_ages: Res<'w, ShowAgeLabels>,
+    currency: Res<'w, Currency>,
+}
+
+impl PigLabelSpawner<'_> {
+    // Because the label is a child of the pig its Transform is relative to the
+    // pig, so it follows the pig around for free. In 2D there is no need to
+    // rotate it towards the camera, it is always facing us.
+    pub fn spawn(&self, pig_children: &mut ChildBuilder, pig: &Pig) {
+        pig_children.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    self.currency.format(pig.value),
+                    TextStyle {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_xyz(0.0, LABEL_OFFSET, 1.0),
+                visibility: label_visibility(self.show_values.0),
+                ..default()
+            },
+            PigLabel,
+            PigValueLabel,
+            Name::new("Pig value label"),
+        ));
+        pig_children.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    age_text(pig),
+                    TextStyle {
+                        font_size: 10.0,
+                        color: Color::YELLOW,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_xyz(0.0, -LABEL_OFFSET, 1.0),
+                visibility: label_visibility(self.show_ages.0),
+                ..default()
+            },
+            PigLabel,
+            PigAgeLabel,
+            Name::new("Pig age label"),
+        ));
+    }
+}
+
+fn toggle_value_labels(input: Res<Input<KeyCode>>, mut show: ResMut<ShowValueLabels>) {
+    if input.just_pressed(KeyCode::V) {
+        show.0 = !show.0;
+    }
+}
+
+// This keeps the text in sync with the pig's value and with the toggle. The
+// Parent component is how a child finds the entity it is attached to.
+fn update_value_labels(
+    mut labels: Query<(&Parent, &mut Text, &mut Visibility), With<PigValueLabel>>,
+    pigs: Query<&Pig>,
+    show: Res<ShowValueLabels>,
+    currency: Res<Currency>,
+) {
+    for (parent, mut text, mut visibility) in &mut labels {
+        *visibility = label_visibility(show.0);
+
+        if let Ok(pig) = pigs.get(parent.get()) {
+            text.sections[0].value = currency.format(pig.value);
+        }
+    }
+}
+
+fn toggle_age_labels(input: Res<Input<KeyCode>>, mut show: ResMut<ShowAgeLabels>) {
+    if input.just_pressed(KeyCode::F2) {
+        show.0 = !show.0;
+    }
+}
+
+fn update_age_labels(
+    mut labels: Query<(&Parent, &mut Text, &mut Visibility), With<PigAgeLabel>>,
+    pigs: Query<&Pig>,
+    show: Res<ShowAgeLabels>,
+) {
+    for (parent, mut text, mut visibility) in &mut labels {
+        *visibility = label_visibility(show.0);
+
+        // No point redoing the text for labels nobody can see.
+        if !show.0 {
+            continue;
+        }
+        if let Ok(pig) = pigs.get(parent.get()) {
+            text.sections[0].value = age_text(pig);
+        }
+    }
+}
+
+// Labels are children of their pig, so when a pig is scaled up or down the
+// text is too. Undoing the pig's scale on the label keeps the text the same
+// size however big the pig is. The label's position still scales, which is
+// handy as it keeps it just above or below the pig as it grows.
+fn keep_labels_full_size(
+    mut labels: Query<(&Parent, &mut Transform), With<PigLabel>>,
+    pigs: Query<&Transform, (With<Pig>, Without<PigLabel>)>,
+) {
+    for (parent, mut transform) in &mut labels {
+        if let Ok(pig) = pigs.get(parent.get()) {
+            transform.scale = pig.scale.recip();
+        }
+    }
+}
+
+// Text that drifts upwards and fades away, like the "+£20" that pops up when a
+// pig is sold. It is despawned once the timer runs out, so they never pile up.
+#[derive(Component)]
+pub struct FloatingText {
+    pub timer: Timer,
+}
+
+// How long a popup lasts and how fast it rises, in pixels per second.
+const FLOATING_TEXT_LIFETIME: f32 = 1.0;
+const FLOATING_TEXT_SPEED: f32 = 30.0;
+
+fn spawn_sale_popups(
+    mut commands: Commands,
+    mut sold: EventReader<PigSoldEvent>,
+    currency: Res<Currency>,
+) {
+    for event in sold.read() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("+{}", currency.format(event.value)),
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::GREEN,
+                        ..default()
+                    },
+                ),
+                // The popup isn't a child of the pig as the pig is about to
+                // be despawned, so it goes wherever the pig was in the world.
+                transform: Transform::from_translation(event.position.extend(10.0)),
+                ..default()
+            },
+            FloatingText {
+                timer: Timer::from_seconds(FLOATING_TEXT_LIFETIME, TimerMode::Once),
+            },
+            Name::new("Sale popup"),
+        ));
+    }
+}
+
+fn float_text(
+    mut commands: Commands,
+    mut texts: Query<(Entity, &mut FloatingText, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut floating, mut transform, mut text) in &mut texts {
+        floating.timer.tick(time.delta());
+        if floating.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.translation.y += FLOATING_TEXT_SPEED * time.delta_seconds();
+        // percent_left goes from 1 down to 0, so the text fades out as the
+        // timer runs down.
+        let alpha = floating.timer.percent_left();
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+// How many seconds the pig has left before it is sold.
+fn age_text(pig: &Pig) -> String {
+    format!("{:.2}s", pig.lifetime.remaining_secs())
+}
+
+fn label_visibility(show: bool) -> Visibility {
+    if show {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
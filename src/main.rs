@@ -1,10 +1,34 @@
 // Importing the main parts of the bevy engine
-use bevy::{prelude::*, core_pipeline::clear_color::ClearColorConfig, input::common_conditions::input_toggle_active};
+use bevy::{prelude::*, core_pipeline::clear_color::ClearColorConfig, diagnostic::FrameTimeDiagnosticsPlugin, input::common_conditions::input_toggle_active, window::PrimaryWindow};
 use bevy_inspector_egui::prelude::*;
 use bevy_inspector_egui::{quick::WorldInspectorPlugin, InspectorOptions};
+mod audio;
+mod camera;
+mod config;
+mod formation;
+mod herd_ring;
+mod interest;
+mod labels;
+mod minimap;
 mod pigs;
+mod save;
+mod screenshot;
+mod settings;
+mod stampede;
 mod ui;
+use audio::GameAudioPlugin;
+use camera::{CameraPlugin, CameraSettings};
+use config::{ConfigPlugin, GameConfig};
+use formation::FormationPlugin;
+use herd_ring::HerdRingPlugin;
+use interest::InterestPlugin;
+use labels::PigLabelPlugin;
+use minimap::MinimapPlugin;
 use pigs::*;
+use save::SavePlugin;
+use screenshot::ScreenshotPlugin;
+use settings::{cycle_currency, toggle_fullscreen, Currency, ImageSettings, KeyBindings, WindowSettings};
+use stampede::StampedePlugin;
 use ui::GameUi;
 // Creating systems are functions that do the actual running of the game but
 // they require a specific set of types as inputes, these can be commands.
@@ -46,10 +70,26 @@ pub struct Camera2Bundle {
 // game is running. 
 // AssetServer is a resource these are single instance services so things that
 // we don't need more than one of like our asset loader and other global data.
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window_settings: Res<WindowSettings>,
+    camera_settings: Res<CameraSettings>,
+    config: Res<GameConfig>,
+) {
+    if window_settings.transparent && !WindowSettings::transparency_supported() {
+        warn!("Transparent windows aren't supported on this platform, using the normal background");
+    }
+
     commands.spawn(Camera2dBundle {
         camera_2d: Camera2d {
-            clear_color: ClearColorConfig::Custom(Color::PURPLE),
+            clear_color: ClearColorConfig::Custom(window_settings.clear_color()),
+        },
+        // The projection's scale is how zoomed out the camera is, we start at
+        // whatever the camera settings ask for.
+        projection: OrthographicProjection {
+            scale: camera_settings.clamp_scale(camera_settings.initial_scale),
+            ..default()
         },
         // ..default effectively says hey I don't care about the rest of the 
         // parameters, they can all use the defaults. 
@@ -79,11 +119,17 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             texture,
             ..default()
         },
-        Player { speed: 100.0},
+        Player {
+            speed: config.player_speed,
+            ..default()
+        },
         Name::new("Player"),
     ));
 }
 
+// The player sprite is 16x16 pixels. 
+const PLAYER_HALF_SIZE: Vec2 = Vec2::new(8.0, 8.0);
+
 // Let's make it so that we can move our character around shall we?
 // We currently only have 1 object with the trait sprite so this query is 
 // adequate. If we had more than one sprite then we could end up having 
@@ -92,27 +138,125 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 // to consider the player's input which comes from the input resource with the
 // input type being KeyCodes. We also make use of time to 
 fn character_movement(
-    mut characters: Query<(&mut Transform, &Player)>,
+    mut characters: Query<(&mut Transform, &mut Sprite, &mut Player)>,
     input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
     time: Res<Time>,
+    // We read the window's size rather than assuming 640x480 so that this
+    // keeps working when the window is resized. 
+    window: Query<&Window, With<PrimaryWindow>>,
+    // Gamepads is the list of connected controllers and Axis holds where each
+    // of their sticks is pointing, from -1 to 1. 
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
 ) {
+    // The play area is one window's worth of space centred on the origin, so
+    // it goes from minus half the window to plus half the window. We take off
+    // half of the sprite so none of the player pokes out of the edge. 
+    let bounds = window
+        .get_single()
+        .map(|window| Vec2::new(window.width(), window.height()) / 2.0 - PLAYER_HALF_SIZE)
+        .ok();
+
     // Here we are looping over all of the entities that match our query and 
     // applying the function below to them. 
-    for (mut transform, player) in &mut characters {
-        let movement_speed = player.speed * time.delta_seconds();
+    for (mut transform, mut sprite, mut player) in &mut characters {
+        let delta = time.delta_seconds();
+        let mut movement_speed = player.speed;
+        // Holding shift makes us sprint. Since we check the key every frame
+        // we start slowing back to normal speed as soon as it is let go.
+        if input.pressed(KeyCode::ShiftLeft) {
+            movement_speed *= player.sprint_multiplier;
+        }
 
-        if input.pressed(KeyCode::W) {
-            transform.translation.y += movement_speed;
+        // We first work out which way the player wants to go, then move them
+        // that way. If we moved along x and y separately then holding two keys
+        // would move us diagonally at about 1.41x the speed.
+        let mut direction = Vec2::ZERO;
+        if input.pressed(keys.up) {
+            direction.y += 1.0;
         }
-        if input.pressed(KeyCode::S) {
-            transform.translation.y -= movement_speed;
+        if input.pressed(keys.down) {
+            direction.y -= 1.0;
         }
-        if input.pressed(KeyCode::A) {
-            transform.translation.x -= movement_speed;
+        if input.pressed(keys.left) {
+            direction.x -= 1.0;
         }
-        if input.pressed(KeyCode::D) {
-            transform.translation.x += movement_speed;
+        if input.pressed(keys.right) {
+            direction.x += 1.0;
+        }
+
+        // normalize_or_zero gives us a direction with a length of 1, or zero
+        // if no keys are held (or opposite keys cancel out), rather than the
+        // NaN that normalising a zero length vector would give us.
+        // The stick is added on top so both work at the same time, then we
+        // cap the length at 1 so using both at once isn't any faster. A stick
+        // that is only tilted a little moves us slower than full speed. 
+        let direction = (direction.normalize_or_zero() + left_stick(&gamepads, &axes))
+            .clamp_length_max(1.0);
+        // Face whichever way we are heading. When we're only going up or down
+        // we leave flip_x alone so the player keeps facing the way they last
+        // went instead of snapping back to the right. 
+        if direction.x < 0.0 {
+            sprite.flip_x = true;
+        } else if direction.x > 0.0 {
+            sprite.flip_x = false;
         }
+
+        // While we're steering, the velocity heads towards the speed we want
+        // to be going. With nothing held, friction brings it back to a stop.
+        // clamp_length_max stops either of them overshooting in one frame. 
+        let change = if direction != Vec2::ZERO {
+            (direction * movement_speed - player.velocity).clamp_length_max(player.acceleration * delta)
+        } else {
+            (-player.velocity).clamp_length_max(player.friction * delta)
+        };
+        player.velocity += change;
+
+        let movement = player.velocity * delta;
+        transform.translation.x += movement.x;
+        transform.translation.y += movement.y;
+
+        // Clamping after moving means the player can never end a frame off
+        // the screen. Running into the edge also stops us moving that way,
+        // otherwise we'd keep pushing into it and be slow to come back. 
+        if let Some(bounds) = bounds {
+            let clamped = transform.translation.truncate().clamp(-bounds, bounds);
+            if clamped.x != transform.translation.x {
+                player.velocity.x = 0.0;
+            }
+            if clamped.y != transform.translation.y {
+                player.velocity.y = 0.0;
+            }
+            transform.translation.x = clamped.x;
+            transform.translation.y = clamped.y;
+        }
+    }
+}
+
+// Sticks never sit perfectly at zero, so anything shorter than this counts as
+// not being touched at all. Otherwise the player would slowly drift about. 
+const STICK_DEADZONE: f32 = 0.1;
+
+// Where the left stick of the first connected controller is pointing, or zero
+// if there isn't one. 
+fn left_stick(gamepads: &Gamepads, axes: &Axis<GamepadAxis>) -> Vec2 {
+    let Some(gamepad) = gamepads.iter().next() else {
+        return Vec2::ZERO;
+    };
+
+    let x = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+        .unwrap_or(0.0);
+    let y = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+        .unwrap_or(0.0);
+    let stick = Vec2::new(x, y);
+
+    if stick.length() < STICK_DEADZONE {
+        Vec2::ZERO
+    } else {
+        stick
     }
 }
 
@@ -124,13 +268,38 @@ fn character_movement(
 
 // Here we are making the player component which contains information about
 // how fast the player moves and at the moment does nothing else.
-#[derive(Component, InspectorOptions, Default, Reflect)]
+#[derive(Component, InspectorOptions, Reflect)]
 #[reflect(Component, InspectorOptions)]
 pub struct Player {
     // This allows us to set a minimum valuie in the debug menu, this way we
     // cannot have negative speed. 
     #[inspector(min=0.0)]
     pub speed: f32,
+    // How much faster we go while holding shift. A minimum of 1 means that
+    // sprinting can never slow us down. 
+    #[inspector(min=1.0)]
+    pub sprint_multiplier: f32,
+    // How fast and which way the player is moving right now. Rather than
+    // jumping straight to full speed the player speeds up by acceleration
+    // every second while a key is held, and slows down by friction every
+    // second once it's let go. 
+    pub velocity: Vec2,
+    #[inspector(min=0.0)]
+    pub acceleration: f32,
+    #[inspector(min=0.0)]
+    pub friction: f32,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player {
+            speed: 100.0,
+            sprint_multiplier: 1.0,
+            velocity: Vec2::ZERO,
+            acceleration: 800.0,
+            friction: 600.0,
+        }
+    }
 }
 
 
@@ -158,6 +327,98 @@ impl Default for Money {
     }
 }
 
+// Numbers we keep track of across a run that aren't part of the game itself,
+// they're just nice to know. total_earned only ever goes up, so it shows how
+// well we're doing even after spending it all on pigs. 
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Stats {
+    pub total_earned: f32,
+}
+
+// States let us split the game up into different modes and only run systems in
+// the modes they make sense in. Deriving States needs all of these other traits
+// as well, and the #[default] variant is the state the game starts in. 
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Playing,
+    Paused,
+    // We've run out of money and have no pigs left to sell, so there's no way
+    // to earn any more. Only restarting gets us out of here. 
+    GameOver,
+}
+
+// NextState is how we ask for a state change, bevy swaps over to the new state
+// at the start of the next frame. 
+fn toggle_pause(
+    input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::P) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        // There's nothing to pause once the game is over. 
+        GameState::GameOver => {}
+    }
+}
+
+// Holding T runs the game at double speed, handy for checking how the economy
+// plays out without waiting around. Time<Virtual> is the game clock that
+// everything else's Time is built on, including the fixed steps the economy
+// runs on, so everything that uses delta_seconds speeds up along with it. 
+fn fast_forward(input: Res<Input<KeyCode>>, mut time: ResMut<Time<Virtual>>) {
+    if input.just_pressed(KeyCode::T) {
+        time.set_relative_speed(2.0);
+    }
+    if input.just_released(KeyCode::T) {
+        time.set_relative_speed(1.0);
+    }
+}
+
+// Pressing R starts the farm over without having to close the game. All the
+// pigs go, our money goes back to what we started with and the player goes
+// back to the middle. despawn_descendants gets rid of everything under the pig
+// parent, including the labels hanging off each pig, but leaves the parent
+// itself alone so new pigs still have somewhere to live. 
+#[allow(clippy::too_many_arguments)]
+fn reset_game(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    parent: Query<Entity, With<PigParent>>,
+    mut money: ResMut<Money>,
+    mut stats: ResMut<Stats>,
+    mut players: Query<(&mut Transform, &mut Player)>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::R) {
+        return;
+    }
+
+    for parent in &parent {
+        commands.entity(parent).despawn_descendants();
+    }
+    *money = Money::default();
+    *stats = Stats::default();
+    for (mut transform, mut player) in &mut players {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        player.velocity = Vec2::ZERO;
+    }
+    // Restarting is the way out of the game over screen. 
+    if *state.get() == GameState::GameOver {
+        next_state.set(GameState::Playing);
+    }
+
+    info!("Game reset");
+}
+
 // Let's use a community plugin, this is a great debugging plugin. 
 
 fn main() {
@@ -167,21 +428,27 @@ fn main() {
 
     // Startup only executes once upon startup.
     // Update executes on every frame. 
+    let window_settings = WindowSettings::from_env();
+    let image_settings = ImageSettings::from_env();
+
     App::new()
         .add_plugins(
             DefaultPlugins
                 // This is us changing some of the defaults so that we can use
                 // non-blurry sprites as well as changing the window name as 
-                // well as changing the resolution and making the window non-
-                // resizable.
-                .set(ImagePlugin::default_nearest())
+                // well as changing the starting resolution. The window can be
+                // resized, everything that cares about its size reads the live
+                // Window rather than assuming 640x480. Sprites are non-blurry
+                // unless linear filtering has been asked for in the image
+                // settings.
+                .set(image_settings.image_plugin())
                 .set(WindowPlugin {
-                    primary_window: Some(Window {
+                    primary_window: Some(window_settings.apply(Window {
                         title: "Test game".into(),
                         resolution: (640.0, 480.0).into(),
-                        resizable: false,
+                        resizable: true,
                         ..default()
-                    }),
+                    })),
                     ..default()
                 })
                 .build(),
@@ -189,7 +456,24 @@ fn main() {
         // All the pig related code has now been moved to a separate file this
         // means that I no longer need to add each system separately that is
         // now all handled within that file. 
-        .add_plugins((PigPlugin, GameUi))
+        .add_plugins((
+            PigPlugin,
+            GameUi,
+            PigLabelPlugin,
+            HerdRingPlugin,
+            CameraPlugin,
+            FormationPlugin,
+            ScreenshotPlugin,
+            GameAudioPlugin,
+            StampedePlugin,
+            InterestPlugin,
+            SavePlugin,
+            ConfigPlugin,
+            MinimapPlugin,
+        ))
+        // Measures how long each frame takes, the FPS readout in the UI is
+        // built on top of this. 
+        .add_plugins(FrameTimeDiagnosticsPlugin)
         // This plugin allows for a really spicy debug menu, but it has gross
         // names, in order to fix that you can add the Name trait to your spawn
         // bundles. 
@@ -201,7 +485,14 @@ fn main() {
                 .run_if(
                     input_toggle_active(true, KeyCode::Escape)),
         )
+        .add_state::<GameState>()
         .init_resource::<Money>()
+        .init_resource::<Stats>()
+        .register_type::<Stats>()
+        .init_resource::<Currency>()
+        .init_resource::<KeyBindings>()
+        .register_type::<KeyBindings>()
+        .insert_resource(window_settings)
         .add_systems(Startup, setup)
         /*
         Systems with the same scheduler can be added in one step by providing
@@ -210,6 +501,21 @@ fn main() {
         .add_systems(Update, spawn_pig)
         .add_systems(Update, pig_lifetime)
         */
-        .add_systems(Update, character_movement)
+        // run_if means the system only runs while its condition is true, so
+        // the player can't move while the game is paused. The UI systems don't
+        // have this so the HUD keeps showing while paused. 
+        .add_systems(
+            Update,
+            (
+                character_movement.run_if(in_state(GameState::Playing)),
+                cycle_currency,
+                toggle_fullscreen,
+                toggle_pause,
+                // Any pig sold this frame is paid out before the reset, not
+                // after, otherwise the new run would start with its money. 
+                reset_game.after(on_pig_sold),
+                fast_forward,
+            ),
+        )
         .run();
 }
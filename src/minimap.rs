@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::GameViewport;
+use crate::pigs::Pig;
+use crate::Player;
+
+// A little map in the corner of the screen with a dot for the player and one for
+// every pig, so you can keep an eye on the whole farm.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_minimap)
+            .add_systems(Update, (update_minimap, fit_minimap_to_viewport));
+    }
+}
+
+#[derive(Component)]
+pub struct Minimap;
+
+// Tag component for the dots inside the minimap. The dots aren't tied to any
+// particular pig, each frame they're just handed out to whatever needs drawing.
+#[derive(Component)]
+pub struct MinimapDot;
+
+const MINIMAP_SIZE: Vec2 = Vec2::new(160.0, 120.0);
+// How far the minimap sits from the bottom right corner of the game area.
+const MINIMAP_MARGIN: f32 = 10.0;
+const DOT_SIZE: f32 = 4.0;
+const PLAYER_DOT_COLOR: Color = Color::YELLOW;
+const PIG_DOT_COLOR: Color = Color::PINK;
+
+fn spawn_minimap(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_MARGIN),
+                bottom: Val::Px(MINIMAP_MARGIN),
+                width: Val::Px(MINIMAP_SIZE.x),
+                height: Val::Px(MINIMAP_SIZE.y),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..default()
+        },
+        Minimap,
+        Name::new("Minimap"),
+    ));
+}
+
+// When the camera is letterboxing, the bars would cover a minimap stuck to the
+// corner of the window, so it is moved into the corner of the game area
+// instead, the same way the HUD bar is.
+fn fit_minimap_to_viewport(
+    viewport: Res<GameViewport>,
+    mut minimaps: Query<&mut Style, With<Minimap>>,
+) {
+    if !viewport.is_changed() {
+        return;
+    }
+
+    for mut style in &mut minimaps {
+        match viewport.0 {
+            Some(rect) => {
+                style.left = Val::Px(rect.max.x - MINIMAP_SIZE.x - MINIMAP_MARGIN);
+                style.top = Val::Px(rect.max.y - MINIMAP_SIZE.y - MINIMAP_MARGIN);
+                style.right = Val::Auto;
+                style.bottom = Val::Auto;
+            }
+            None => {
+                style.left = Val::Auto;
+                style.top = Val::Auto;
+                style.right = Val::Px(MINIMAP_MARGIN);
+                style.bottom = Val::Px(MINIMAP_MARGIN);
+            }
+        }
+    }
+}
+
+// Rather than spawning and despawning dots every frame, we keep the ones we
+// already have and just move them. Dots are only added or removed when the
+// number of pigs changes, so this stays cheap even with lots of pigs.
+fn update_minimap(
+    mut commands: Commands,
+    minimap: Query<Entity, With<Minimap>>,
+    mut dots: Query<(Entity, &mut Style, &mut BackgroundColor), With<MinimapDot>>,
+    pigs: Query<&GlobalTransform, With<Pig>>,
+    player: Query<&GlobalTransform, With<Player>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(minimap) = minimap.get_single() else {
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    // The play area is one window's worth of space centred on the origin, so
+    // that is what the minimap shows.
+    let world_size = Vec2::new(window.width(), window.height());
+
+    // The player goes first so it is always drawn in its own colour.
+    let points: Vec<(Vec2, Color)> = player
+        .iter()
+        .map(|transform| (transform.translation().truncate(), PLAYER_DOT_COLOR))
+        .chain(
+            pigs.iter()
+                .map(|transform| (transform.translation().truncate(), PIG_DOT_COLOR)),
+        )
+        .collect();
+
+    let mut dots = dots.iter_mut();
+    for (position, color) in &points {
+        let (left, top) = to_minimap(*position, world_size);
+        match dots.next() {
+            Some((_, mut style, mut background)) => {
+                style.left = left;
+                style.top = top;
+                background.0 = *color;
+            }
+            // We've run out of dots, so we make a new one. It won't show up
+            // until the commands are applied, which is fine for a minimap.
+            None => {
+                let dot = commands
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left,
+                                top,
+                                width: Val::Px(DOT_SIZE),
+                                height: Val::Px(DOT_SIZE),
+                                ..default()
+                            },
+                            background_color: (*color).into(),
+                            ..default()
+                        },
+                        MinimapDot,
+                    ))
+                    .id();
+                commands.entity(minimap).add_child(dot);
+            }
+        }
+    }
+
+    // Anything left over belongs to a pig that has gone.
+    for (dot, _, _) in dots {
+        commands.entity(dot).despawn_recursive();
+    }
+}
+
+// World positions have y going up with the origin in the middle, UI positions
+// have y going down with the origin in the top left, so we flip and shift. The
+// dot is kept inside the minimap even if something wanders off the edge.
+fn to_minimap(position: Vec2, world_size: Vec2) -> (Val, Val) {
+    let fraction = Vec2::new(
+        position.x / world_size.x + 0.5,
+        0.5 - position.y / world_size.y,
+    )
+    .clamp(Vec2::ZERO, Vec2::ONE);
+    let local = fraction * (MINIMAP_SIZE - DOT_SIZE);
+    (Val::Px(local.x), Val::Px(local.y))
+}
@@ -1,6 +1,13 @@
 use crate::Player;
-use crate::Money;
+use crate::{Money, Stats};
+use crate::GameState;
+use crate::config::GameConfig;
+use crate::labels::PigLabelSpawner;
+use crate::settings::{Currency, KeyBindings};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+use rand::Rng;
 
 // To create a plugin I just need a unit struct that has the Plugin trait 
 // implemented. Plugins can include other plugins so if one plugin requires
@@ -12,9 +19,78 @@ impl Plugin for PigPlugin {
     // it via .add_plugins(), this massively reduces the amount of boilerplate
     // needed. 
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_pig_parent)
-            .add_systems(Update, (spawn_pig, pig_lifetime))
-            .register_type::<Pig>();
+        app.init_resource::<AutoSellRule>()
+            .init_resource::<Upkeep>()
+            .init_resource::<CantAffordFeedback>()
+            .init_resource::<SalesTax>()
+            .init_resource::<TaxCollected>()
+            .init_resource::<PayoutLimits>()
+            .init_resource::<WanderSettings>()
+            .init_resource::<PigFollow>()
+            .init_resource::<PigLimit>()
+            .init_resource::<SpawnCharge>()
+            .init_resource::<PigPrice>()
+            .init_resource::<PriceScaling>()
+            .init_resource::<PigCounter>()
+            .add_event::<CantAffordPig>()
+            .add_event::<BuyPig>()
+            .add_event::<PigSoldEvent>()
+            .add_systems(Startup, spawn_pig_parent)
+            // pig_lifetime ages the pigs in FixedUpdate, which runs a set
+            // number of times per second of game time no matter the frame
+            // rate, so pigs age and sell exactly the same on a slow machine as
+            // on a fast one. Its despawns are applied at the end of
+            // FixedUpdate, before Update runs. 
+            // None of the pig systems run while the game is paused, so timers
+            // don't tick, pigs don't move and we can't buy any more. 
+            .add_systems(
+                FixedUpdate,
+                (pig_lifetime, pig_upkeep).run_if(in_state(GameState::Playing)),
+            )
+            // auto_sell and sell_all can both pick the same pig in one frame.
+            // Chaining only decides the order they run in, auto_sell's despawns
+            // haven't been applied yet when sell_all runs, so sell_all still
+            // sees those pigs. The sold flag on each pig is what stops it being
+            // paid for twice. on_pig_sold goes last so sales are paid out in
+            // the same frame they happen. It stays in Update as an event could
+            // be missed by a FixedUpdate system on a frame where FixedUpdate
+            // doesn't run. 
+            .add_systems(
+                Update,
+                (
+                    (update_pig_price, charge_spawn, spawn_pig).chain(),
+                    (auto_sell, sell_all, on_pig_sold).chain(),
+                    move_to_target,
+                    pig_wander,
+                    pig_follow,
+                    pig_separation,
+                    pig_growth,
+                    // Buying and selling pigs both use commands, which aren't
+                    // applied just because a system runs after them. Without
+                    // apply_deferred the check could see the money spent on a
+                    // pig but not the pig itself, and end the game on the
+                    // frame we buy our last pig. 
+                    (apply_deferred, check_game_over)
+                        .chain()
+                        .after(spawn_pig)
+                        .after(on_pig_sold),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .register_type::<Pig>()
+            .register_type::<PigBreed>()
+            .register_type::<MoveTarget>()
+            .register_type::<AutoSellRule>()
+            .register_type::<Upkeep>()
+            .register_type::<CantAffordFeedback>()
+            .register_type::<SalesTax>()
+            .register_type::<PayoutLimits>()
+            .register_type::<WanderSettings>()
+            .register_type::<PigFollow>()
+            .register_type::<PigLimit>()
+            .register_type::<PigPrice>()
+            .register_type::<PriceScaling>()
+            .register_type::<PigCounter>();
     }
 }
 
@@ -27,18 +103,286 @@ impl Plugin for PigPlugin {
 #[reflect(Component)]
 pub struct Pig {
     pub lifetime: Timer,
+    // How much money the pig is worth when it is sold. This lives on the pig
+    // itself so that anything that changes a pig's worth only needs to touch
+    // this one field. 
+    pub value: f32,
+    // What the pig will be worth once it has lived out its whole lifetime,
+    // before its breed's payout multiplier. Its value grows towards this as it
+    // gets older. 
+    pub max_value: f32,
+    // Which way the pig is currently wandering and how long until it picks a
+    // new direction. 
+    pub wander_direction: Vec2,
+    pub wander_timer: Timer,
+    pub breed: PigBreed,
+    // Set the moment the pig is sold. The pig itself hangs around until the
+    // despawn command is applied, so until then this is what stops a second
+    // system from selling it again and paying us twice. 
+    pub sold: bool,
 }
 
+// Not every pig is the same. Rarer breeds cost more to buy but pay out a lot
+// more when they're sold. Which breed we get is random, weighted so that most
+// pigs are common. 
+#[derive(Clone, Copy, Default, Debug, PartialEq, Reflect)]
+pub enum PigBreed {
+    #[default]
+    Common,
+    Rare,
+    Golden,
+}
+
+impl PigBreed {
+    const ALL: [PigBreed; 3] = [PigBreed::Common, PigBreed::Rare, PigBreed::Golden];
+
+    pub fn texture(self) -> &'static str {
+        match self {
+            PigBreed::Common => "pig.png",
+            PigBreed::Rare => "pig_rare.png",
+            PigBreed::Golden => "pig_golden.png",
+        }
+    }
+
+    // The pig price is multiplied by this when we buy one of these.
+    pub fn cost_multiplier(self) -> f32 {
+        match self {
+            PigBreed::Common => 1.0,
+            PigBreed::Rare => 1.5,
+            PigBreed::Golden => 2.0,
+        }
+    }
+
+    // The pig's value is multiplied by this, so it's worth more the whole time
+    // it's alive, not just when it's sold.
+    pub fn payout_multiplier(self) -> f32 {
+        match self {
+            PigBreed::Common => 1.0,
+            PigBreed::Rare => 2.0,
+            PigBreed::Golden => 5.0,
+        }
+    }
+
+    // How likely each breed is compared to the others. Out of every 100 pigs
+    // about 80 are common, 15 rare and 5 golden.
+    fn weight(self) -> u32 {
+        match self {
+            PigBreed::Common => 80,
+            PigBreed::Rare => 15,
+            PigBreed::Golden => 5,
+        }
+    }
+
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let total: u32 = Self::ALL.iter().map(|breed| breed.weight()).sum();
+        let mut roll = rng.gen_range(0..total);
+        for breed in Self::ALL {
+            if roll < breed.weight() {
+                return breed;
+            }
+            roll -= breed.weight();
+        }
+        PigBreed::Common
+    }
+}
+
+// Pigs amble about while they are alive. They pick a random direction, walk
+// that way for a second or so, then pick another. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct WanderSettings {
+    pub speed: f32,
+    // How far from the pig parent a pig is allowed to wander before it turns
+    // back, this keeps the herd from drifting off the screen. While pigs are
+    // following the player it's measured from the player instead. 
+    pub radius: f32,
+}
+
+impl Default for WanderSettings {
+    fn default() -> Self {
+        WanderSettings {
+            speed: 20.0,
+            radius: 200.0,
+        }
+    }
+}
+
+// Any pig can be told to walk somewhere by giving it a target. Whatever wants
+// the pig to move just sets the target and move_to_target does the walking,
+// so that logic isn't copied into every feature that moves pigs around. 
+// The target is relative to the pig parent, which sits at the origin. 
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct MoveTarget(pub Option<Vec2>);
+
+// How fast pigs walk to their target and how close they have to get before
+// they count as having arrived. 
+const PIG_WALK_SPEED: f32 = 60.0;
+const ARRIVAL_RADIUS: f32 = 2.0;
+
 // You can also add the "special" trait FromWorld which allows us to create 
 // resources that have access to the entire bevy ECS world. This is moslty
 // useful for things like rendering. 
 
+// Events are messages that one system sends and any number of other systems
+// can read. spawn_pig sends this when we try to buy a pig we can't afford, and
+// the UI and audio each react to it in their own way without spawn_pig having
+// to know about either of them. 
+#[derive(Event)]
+pub struct CantAffordPig;
+
+// Sent by anything that wants to buy pigs, like the spawn key or the button in
+// the UI. spawn_pig does the actual buying, so every way of buying a pig
+// follows the same rules. 
+#[derive(Event)]
+pub struct BuyPig {
+    pub count: usize,
+    // Free pigs don't cost anything, loading a save uses these to give us
+    // back the herd we already paid for. 
+    pub free: bool,
+}
+
+// Which kinds of feedback to give when we can't afford a pig. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CantAffordFeedback {
+    // Flash the money text red.
+    pub flash: bool,
+    // Play the error sound.
+    pub sound: bool,
+}
+
+impl Default for CantAffordFeedback {
+    fn default() -> Self {
+        CantAffordFeedback {
+            flash: true,
+            sound: true,
+        }
+    }
+}
+
+// The most pigs we're allowed to have at once. Without a cap the farm can grow
+// until the game grinds to a halt. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PigLimit(pub usize);
+
+impl Default for PigLimit {
+    fn default() -> Self {
+        PigLimit(100)
+    }
+}
+
+// A newborn pig is worth the pig_payout from the config, and each second a pig
+// is set to live adds PIG_VALUE_PER_SECOND to what it is worth by the end. So
+// a pig that lives for 5 seconds pays out more than one that lives for 1. 
+const PIG_VALUE_PER_SECOND: f32 = 5.0;
+
+// How long, in seconds, the spawn key is held for what we buy when it is let
+// go. SECONDS_PER_EXTRA_PIG of holding adds one more pig to the order. 
+#[derive(Resource, Default)]
+pub struct SpawnCharge(pub f32);
+
+const SECONDS_PER_EXTRA_PIG: f32 = 0.25;
+
+// Counts every pig we've ever bought so each one gets its own number in its
+// name, which makes them much easier to tell apart in the inspector. It never
+// goes down, even when pigs are sold or the game is reset, so no two pigs
+// share a number. A u64 won't run out however long you play. 
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct PigCounter(pub u64);
+
+// How far from the player a new pig can appear. 
+const SPAWN_RADIUS: f32 = 24.0;
+
+// How much it costs to buy the next pig. This is a resource rather than a
+// constant so that it can change while the game is running, update_pig_price
+// works it out from PriceScaling. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PigPrice(pub f32);
+
+impl Default for PigPrice {
+    fn default() -> Self {
+        PigPrice(10.0)
+    }
+}
+
+// The more pigs we already own, the more the next one costs. The price is the
+// pig_price from the config plus per_pig for every pig we have, but never
+// more than max. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PriceScaling {
+    pub per_pig: f32,
+    pub max: f32,
+}
+
+impl Default for PriceScaling {
+    fn default() -> Self {
+        PriceScaling {
+            per_pig: 0.5,
+            max: 100.0,
+        }
+    }
+}
+
+impl PriceScaling {
+    pub fn price_for(&self, base: f32, pig_count: usize) -> f32 {
+        (base + self.per_pig * pig_count as f32).min(self.max)
+    }
+}
+
+// Keeps the price up to date as pigs come and go. spawn_pig also bumps the
+// price after each pig it buys, so a bulk buy gets dearer as it goes rather
+// than every pig costing what the first one did. 
+fn update_pig_price(
+    scaling: Res<PriceScaling>,
+    config: Res<GameConfig>,
+    pigs: Query<(), With<Pig>>,
+    mut price: ResMut<PigPrice>,
+) {
+    let new_price = scaling.price_for(config.pig_price, pigs.iter().count());
+    // Only write when it actually changes so the price isn't marked as
+    // changed every frame. 
+    if price.0 != new_price {
+        price.0 = new_price;
+    }
+}
+
+// Holding the spawn key charges up a bulk buy, and letting go buys one pig plus
+// an extra one for every SECONDS_PER_EXTRA_PIG it was held. A quick tap is let
+// go before any extras build up, so it buys exactly one. 
+// We only act on the release, so holding the key down doesn't buy a pig every
+// frame, think the double jump issue we had when messing around with unity. 
+fn charge_spawn(
+    input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut charge: ResMut<SpawnCharge>,
+    time: Res<Time>,
+    mut buy: EventWriter<BuyPig>,
+) {
+    if input.pressed(keys.spawn_pig) {
+        charge.0 += time.delta_seconds();
+        return;
+    }
+
+    if input.just_released(keys.spawn_pig) {
+        let count = 1 + (charge.0 / SECONDS_PER_EXTRA_PIG) as usize;
+        buy.send(BuyPig { count, free: false });
+    }
+    // Resetting whenever the key is up also throws away a charge that was let
+    // go while the game was paused and this system wasn't running. 
+    charge.0 = 0.0;
+}
+
 // Let's add some actual "gameplay" shall we? This allows us to spawn pigs at
-// the cost of 10 dollars every time we press the spacebar. 
+// the cost of PigPrice every time we press the spacebar. 
+#[allow(clippy::too_many_arguments)]
 fn spawn_pig(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    input: Res<Input<KeyCode>>,
     // Mutability needed since we are changing the amount of money that we have
     // otherwise we would be creating pigs for free. 
     mut money: ResMut<Money>,
@@ -54,11 +398,32 @@ fn spawn_pig(
     // currently has write access. 
     player: Query<&Transform, With<Player>>,
     parent: Query<Entity, With<PigParent>>,
+    currency: Res<Currency>,
+    mut cant_afford: EventWriter<CantAffordPig>,
+    mut buy: EventReader<BuyPig>,
+    pigs: Query<(), With<Pig>>,
+    limit: Res<PigLimit>,
+    mut price: ResMut<PigPrice>,
+    scaling: Res<PriceScaling>,
+    config: Res<GameConfig>,
+    mut counter: ResMut<PigCounter>,
+    labels: PigLabelSpawner,
+    limits: Res<PayoutLimits>,
 ) {
-    // This forces the function to skip out on the rest of the function if we 
-    // have already pressed the spacebar recently, think the double jump issue
-    // we had when messing around with unity. 
-    if !input.just_pressed(KeyCode::Space) {
+    // Add up every order placed this frame, from the key, the button and
+    // loading a save. The free pigs are handed out first. 
+    let mut free = 0;
+    let mut count = 0;
+    for order in buy.read() {
+        if order.free {
+            free += order.count;
+        }
+        count += order.count;
+    }
+
+    // This forces the function to skip out on the rest of the function if
+    // there is nothing to buy this frame. 
+    if count == 0 {
         return;
     }
 
@@ -72,11 +437,67 @@ fn spawn_pig(
     let player_transform = player.single();
     let parent = parent.single();
 
-    if money.0 >= 10.0 {
-        money.0 -= 10.0;
-        info!("Spent £10 on a pig, you now have: £{:?}", money.0);
+    // The pigs we spawn below don't exist until the commands are applied, so
+    // we keep our own count as we go. 
+    let existing = pigs.iter().count();
+
+    for (bought, pig_count) in (existing..existing + count).enumerate() {
+        // We check the cap before the money so a full farm never costs us
+        // anything. 
+        if pig_count >= limit.0 {
+            info!("You already have {} pigs, that's as many as the farm can hold", limit.0);
+            return;
+        }
+
+        // The save doesn't remember breeds, so free pigs are always common.
+        // Otherwise reloading would be a way to reroll for golden pigs. 
+        let breed = if bought < free {
+            PigBreed::Common
+        } else {
+            // If we roll a fancy breed we can't afford we get a common pig
+            // instead, rather than nothing at all. 
+            let mut breed = PigBreed::random(&mut rand::thread_rng());
+            if money.0 < price.0 * breed.cost_multiplier() {
+                breed = PigBreed::Common;
+            }
+            let cost = price.0 * breed.cost_multiplier();
+
+            // Once we run out of money we stop buying, whatever pigs we could
+            // afford are kept. 
+            if money.0 < cost {
+                cant_afford.send(CantAffordPig);
+                return;
+            }
+
+            money.0 -= cost;
+            info!(
+                "Spent {} on a {:?} pig, you now have: {}",
+                currency.format(cost),
+                breed,
+                currency.format(money.0)
+            );
+            breed
+        };
+        price.0 = scaling.price_for(config.pig_price, pig_count + 1);
+
+        let texture: Handle<Image> = asset_server.load(breed.texture());
+        counter.0 += 1;
+
+        // Every pig lives for a different amount of time so they don't all
+        // sell like clockwork. 
+        let lifetime = rand::thread_rng().gen_range(config.lifetime_range());
 
-        let texture: Handle<Image> = asset_server.load("pig.png");
+        // Each pig lands somewhere random within SPAWN_RADIUS of the player
+        // rather than right on top of them, so a bulk buy doesn't look like a
+        // single pig. Taking the square root of the distance spreads the pigs
+        // evenly over the circle instead of bunching them in the middle. 
+        let mut rng = rand::thread_rng();
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let distance = rng.gen_range(0.0f32..1.0).sqrt() * SPAWN_RADIUS;
+        let offset = Vec2::from_angle(angle) * distance;
+        let mut transform = player_transform.with_scale(Vec3::splat(PIG_START_SCALE));
+        transform.translation.x += offset.x;
+        transform.translation.y += offset.y;
 
         // This spawns a pig text at the players location, this is the 
         // implementation if we are not using a parent. 
@@ -98,71 +519,502 @@ fn spawn_pig(
         ));
         */
 
+        let pig = Pig {
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+            value: limits.apply(config.pig_payout * breed.payout_multiplier()),
+            max_value: config.pig_payout + PIG_VALUE_PER_SECOND * lifetime,
+            breed,
+            // Starting with a finished timer means the pig picks its first
+            // direction straight away. 
+            wander_timer: Timer::from_seconds(0.0, TimerMode::Once),
+            ..default()
+        };
+
         // This spawns a pig if we are using a parent to spawn child pigs. 
         // This basically says .with_children(|child builder|) { how to build }
         commands.entity(parent).with_children(|commands| {
-            commands.spawn((
-                SpriteBundle {
-                    texture,
-                    transform: *player_transform,
-                    ..default()
-                },
-                Pig {
-                    lifetime: Timer::from_seconds(1.0, TimerMode::Once),
-                },
-                Name::new("Pig"),
-            ));
+            commands
+                .spawn((
+                    SpriteBundle {
+                        texture,
+                        // Pigs are born small, pig_growth takes it from here. 
+                        transform,
+                        ..default()
+                    },
+                    MoveTarget::default(),
+                    Name::new(format!("Pig #{}", counter.0)),
+                ))
+                // The pig's labels are its own children, so they are spawned
+                // right along with it. The labels need to see the pig to know
+                // what to say, so the Pig component goes on last. 
+                .with_children(|pig_children| labels.spawn(pig_children, &pig))
+                .insert(pig);
         });
     }
 }
 
 // This system is used to keep track of the pig's timer. 
+// Time<Fixed> is the clock FixedUpdate runs on, each tick it moves forward by
+// the same fixed step. 
 fn pig_lifetime(
-    mut commands: Commands,
-    time: Res<Time>,
+    time: Res<Time<Fixed>>,
     // Note that entity is special and is the only thing we have in the first
     // part of a query that doesn't need to be used as a reference. 
     mut pigs: Query<(Entity, &mut Pig)>,
-    // Spawn pig and the pig_lifetime systems both mutably acces money, this 
-    // means that we will have a block here. But, since these are both very 
-    // small systems it is unlikely that this will cause issues. But, for large
-    // systems that take a long time to resolve this could be an issue. 
-    mut money: ResMut<Money>,
-    parent: Query<Entity, With<PigParent>>,
+    // Selling needs mutable access to a few resources, like the tax total. Any
+    // other system that wants to write to those will have to wait for this one
+    // to finish, which is a block. But, since these are very small systems it
+    // is unlikely that this will cause issues. But, for large systems that
+    // take a long time to resolve this could be an issue. 
+    mut sales: PigSales,
+    config: Res<GameConfig>,
+    limits: Res<PayoutLimits>,
 ) {
-    let parent = parent.single();
     for (pig_entity, mut pig) in &mut pigs {
         pig.lifetime.tick(time.delta());
 
+        // The pig's value grows from the base value up to its max value as it
+        // ages, percent() goes from 0 when the pig is born to 1 when the timer
+        // runs out. Then the breed multiplies the lot, and the payout limits
+        // keep the result from going anywhere silly. 
+        let growth = pig.lifetime.percent();
+        let value = config.pig_payout + (pig.max_value - config.pig_payout) * growth;
+        pig.value = limits.apply(value * pig.breed.payout_multiplier());
+
         if pig.lifetime.finished() {
-            money.0 += 20.0;
-            // commands.entity returns us a data type that allows us to make a
-            // variety of changes to the entity that we pass it. We can add 
-            // components to them, fetch their ids and various other 
-            // functionalities. 
-            // In this case we are simply despawning them. 
-            // This is how we do that without a parent. 
-            /* 
-            commands.entity(pig_entity).despawn();
-            */
-
-            // This is how we do that with a parent. First we get the entity
-            // for the parent, we then remove the child by passing a slice of
-            // the child entity. 
-            commands.entity(parent).remove_children(&[pig_entity]);
-            // We then despawn the pig entity in the same way as we do above,
-            // if I don't remove child the list will get larger and larger 
-            // creating a sort of memory leak, the parent won't ever try to 
-            // actually access the child if you don't delete it. And if we 
-            // iterate over the list we will be iterating over dead entities. 
-            commands.entity(pig_entity).despawn();
-
-            // This logs to the console. 
-            info!("Pig sold for £20! Current money: £{:?}", money.0);
+            sales.sell(pig_entity, &mut pig);
+        }
+    }
+}
+
+// A percentage taken off every sale. It starts at 0 so nothing changes unless
+// we turn it up. 
+#[derive(Resource, Default, Reflect, InspectorOptions)]
+#[reflect(Resource, InspectorOptions)]
+pub struct SalesTax {
+    #[inspector(min = 0.0, max = 100.0)]
+    pub percent: f32,
+}
+
+// However a pig's value and payout end up being worked out, they always land
+// between these two, so no combination of modifiers can pay out something
+// silly. The value is kept inside them as the pig ages, and the payout again
+// after tax. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PayoutLimits {
+    pub floor: f32,
+    pub ceiling: f32,
+}
+
+impl Default for PayoutLimits {
+    fn default() -> Self {
+        PayoutLimits {
+            floor: 0.0,
+            ceiling: 1_000_000.0,
+        }
+    }
+}
+
+impl PayoutLimits {
+    // f32::clamp panics if the floor is above the ceiling, which is easy to do
+    // by accident in the inspector, so we apply each limit on its own.
+    pub fn apply(&self, payout: f32) -> f32 {
+        payout.max(self.floor).min(self.ceiling)
+    }
+}
+
+// The running total of money the tax has taken from us. 
+#[derive(Resource, Default)]
+pub struct TaxCollected(pub f32);
+
+// Pigs can be sold for more than one reason, so the actual selling lives in
+// one place and every system that sells a pig goes through here. 
+// SystemParam lets us bundle up everything a sale needs into one parameter,
+// so a system only has to ask for PigSales rather than each piece separately. 
+#[derive(SystemParam)]
+pub struct PigSales<'w, 's> {
+    commands: Commands<'w, 's>,
+    parent: Query<'w, 's, Entity, With<PigParent>>,
+    sold: EventWriter<'w, PigSoldEvent>,
+    currency: Res<'w, Currency>,
+    tax: Res<'w, SalesTax>,
+    tax_collected: ResMut<'w, TaxCollected>,
+    limits: Res<'w, PayoutLimits>,
+    transforms: Query<'w, 's, &'static GlobalTransform, With<Pig>>,
+}
+
+impl PigSales<'_, '_> {
+    // Returns how much the pig sold for after tax, or None if it had already
+    // been sold. 
+    pub fn sell(&mut self, pig_entity: Entity, pig: &mut Pig) -> Option<f32> {
+        if pig.sold {
+            return None;
+        }
+        pig.sold = true;
+
+        let tax = pig.value * self.tax.percent / 100.0;
+        let payout = self.limits.apply(pig.value - tax);
+        self.tax_collected.0 += tax;
+        // We don't touch our money here, we just announce that a pig was sold
+        // and on_pig_sold takes care of the money. 
+        // GlobalTransform is where the pig is in the world, rather than where
+        // it is relative to the pig parent. 
+        let position = self
+            .transforms
+            .get(pig_entity)
+            .map(|transform| transform.translation().truncate())
+            .unwrap_or_default();
+        self.sold.send(PigSoldEvent { value: payout, position });
+
+        // commands.entity returns us a data type that allows us to make a
+        // variety of changes to the entity that we pass it. We can add 
+        // components to them, fetch their ids and various other 
+        // functionalities. 
+        // In this case we are simply despawning them. 
+        // This is how we do that without a parent. 
+        /* 
+        commands.entity(pig_entity).despawn();
+        */
+
+        // This is how we do that with a parent. First we get the entity
+        // for the parent, we then remove the child by passing a slice of
+        // the child entity. 
+        let parent = self.parent.single();
+        self.commands.entity(parent).remove_children(&[pig_entity]);
+        // We then despawn the pig entity in the same way as we do above,
+        // if I don't remove child the list will get larger and larger 
+        // creating a sort of memory leak, the parent won't ever try to 
+        // actually access the child if you don't delete it. And if we 
+        // iterate over the list we will be iterating over dead entities. 
+        // We use despawn_recursive so that anything hanging off the pig,
+        // like its value label, is cleaned up along with it. 
+        self.commands.entity(pig_entity).despawn_recursive();
+
+        if tax > 0.0 {
+            info!("Paid {} tax on a pig sale", self.currency.format(tax));
+        }
+
+        Some(payout)
+    }
+}
+
+// Sent whenever a pig is sold, value is how much money it made after tax.
+// Anything that wants to react to a sale can read these without having to know
+// about every way a pig can be sold. 
+#[derive(Event)]
+pub struct PigSoldEvent {
+    pub value: f32,
+    // Where in the world the pig was when it was sold.
+    pub position: Vec2,
+}
+
+pub fn on_pig_sold(
+    mut sold: EventReader<PigSoldEvent>,
+    mut money: ResMut<Money>,
+    mut stats: ResMut<Stats>,
+    currency: Res<Currency>,
+) {
+    for event in sold.read() {
+        money.0 += event.value;
+        stats.total_earned += event.value;
+
+        // This logs to the console. 
+        info!(
+            "Pig sold for {}! Current money: {}",
+            currency.format(event.value),
+            currency.format(money.0)
+        );
+    }
+}
+
+// An optional rule for hands-off play: any pig that is worth more than
+// min_value gets sold straight away instead of waiting out its lifetime. 
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct AutoSellRule {
+    pub enabled: bool,
+    pub min_value: f32,
+}
+
+fn auto_sell(rule: Res<AutoSellRule>, mut pigs: Query<(Entity, &mut Pig)>, mut sales: PigSales) {
+    if !rule.enabled {
+        return;
+    }
+
+    for (pig_entity, mut pig) in &mut pigs {
+        if pig.value > rule.min_value {
+            sales.sell(pig_entity, &mut pig);
+        }
+    }
+}
+
+// Pressing L sells every pig right now at whatever it is currently worth,
+// handy for testing or for cashing out before a big purchase. 
+fn sell_all(
+    input: Res<Input<KeyCode>>,
+    mut pigs: Query<(Entity, &mut Pig)>,
+    currency: Res<Currency>,
+    mut sales: PigSales,
+) {
+    if !input.just_pressed(KeyCode::L) || pigs.is_empty() {
+        return;
+    }
+
+    // Pigs that something else already sold this frame are skipped, so they
+    // don't count towards the total. 
+    let mut total = 0.0;
+    let mut count = 0;
+    for (pig_entity, mut pig) in &mut pigs {
+        if let Some(payout) = sales.sell(pig_entity, &mut pig) {
+            total += payout;
+            count += 1;
+        }
+    }
+    info!("Sold all {} pigs for {}", count, currency.format(total));
+}
+
+fn move_to_target(mut pigs: Query<(&mut Transform, &MoveTarget)>, time: Res<Time>) {
+    for (mut transform, target) in &mut pigs {
+        if let Some(target) = target.0 {
+            move_toward(&mut transform, target, PIG_WALK_SPEED, time.delta_seconds());
+        }
+    }
+}
+
+fn pig_wander(
+    mut pigs: Query<(&mut Transform, &mut Pig, &MoveTarget)>,
+    settings: Res<WanderSettings>,
+    follow: Res<PigFollow>,
+    player: Query<&Transform, (With<Player>, Without<Pig>)>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    // Normally pigs stay near the middle of the farm, but while they're
+    // following the player they stay near the player instead. Otherwise the
+    // two would fight, with wandering dragging pigs back to the middle as
+    // fast as following pulls them away. 
+    let centre = match player.get_single() {
+        Ok(player) if follow.enabled => player.translation.truncate(),
+        _ => Vec2::ZERO,
+    };
+
+    for (mut transform, mut pig, target) in &mut pigs {
+        // A pig that has somewhere to be doesn't get distracted. 
+        if target.0.is_some() {
+            continue;
+        }
+
+        pig.wander_timer.tick(time.delta());
+        if pig.wander_timer.finished() {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            pig.wander_direction = Vec2::from_angle(angle);
+            pig.wander_timer = Timer::from_seconds(rng.gen_range(0.5..1.5), TimerMode::Once);
+        }
+
+        // The pig's Transform is relative to the pig parent, which sits at
+        // the origin, so it's the same as where the pig is in the world. If it
+        // has gone too far we point it back towards the centre. 
+        let offset = transform.translation.truncate() - centre;
+        if offset.length() > settings.radius {
+            pig.wander_direction = -offset.normalize();
+        }
+
+        let step = pig.wander_direction * settings.speed * time.delta_seconds();
+        transform.translation.x += step.x;
+        transform.translation.y += step.y;
+    }
+}
+
+// Pigs slowly drift towards the player, like they're being herded. They stop
+// once they're within min_distance so they don't all pile on top of us. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PigFollow {
+    pub enabled: bool,
+    pub speed: f32,
+    pub min_distance: f32,
+}
+
+impl Default for PigFollow {
+    fn default() -> Self {
+        PigFollow {
+            enabled: true,
+            speed: 10.0,
+            min_distance: 32.0,
+        }
+    }
+}
+
+// This moves the pigs on top of whatever wandering they're doing, so a
+// following pig still ambles about, it just drifts our way as it does. 
+fn pig_follow(
+    follow: Res<PigFollow>,
+    player: Query<&Transform, (With<Player>, Without<Pig>)>,
+    mut pigs: Query<(&mut Transform, &MoveTarget), With<Pig>>,
+    time: Res<Time>,
+) {
+    if !follow.enabled {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let player_position = player.translation.truncate();
+
+    for (mut transform, target) in &mut pigs {
+        // Pigs that have been told to go somewhere else ignore us. 
+        if target.0.is_some() {
+            continue;
+        }
+
+        let to_player = player_position - transform.translation.truncate();
+        let distance = to_player.length();
+        if distance <= follow.min_distance {
+            continue;
+        }
+
+        // Never step past the point where we'd stop anyway. 
+        let step = (follow.speed * time.delta_seconds()).min(distance - follow.min_distance);
+        let movement = to_player / distance * step;
+        transform.translation.x += movement.x;
+        transform.translation.y += movement.y;
+    }
+}
+
+// A newborn pig is drawn at PIG_START_SCALE times its full size and grows to
+// PIG_END_SCALE by the time it is sold, so you can see which pigs are nearly
+// ready to pay out. 
+const PIG_START_SCALE: f32 = 0.5;
+const PIG_END_SCALE: f32 = 1.0;
+
+fn pig_growth(mut pigs: Query<(&mut Transform, &Pig)>) {
+    for (mut transform, pig) in &mut pigs {
+        let scale = PIG_START_SCALE + (PIG_END_SCALE - PIG_START_SCALE) * pig.lifetime.percent();
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+// Pigs closer together than this are pushed apart. The pig sprite is 32 by 16
+// pixels and this is half its height, so pigs can overlap a little side to
+// side but never stack up.
+const PIG_RADIUS: f32 = 8.0;
+// Every pig is checked against every other pig, so the work grows with the
+// square of the herd. After this many checks in one frame we stop, which keeps
+// a huge herd from bringing the game to a halt. With the default pig limit we
+// never get close, and if we do the pigs we skip just overlap for a while.
+const MAX_SEPARATION_CHECKS: usize = 20_000;
+
+// Nudges overlapping pigs away from each other. It isn't real physics, each
+// pig of an overlapping pair just moves half of the overlap away from the
+// other so they end up side by side.
+fn pig_separation(mut pigs: Query<&mut Transform, With<Pig>>) {
+    let mut rng = rand::thread_rng();
+
+    // iter_combinations_mut hands us every pair of pigs exactly once. It
+    // can't be a normal iterator as we get mutable access to both pigs.
+    let mut pairs = pigs.iter_combinations_mut();
+    let mut checks = 0;
+    while let Some([mut a, mut b]) = pairs.fetch_next() {
+        checks += 1;
+        if checks > MAX_SEPARATION_CHECKS {
+            break;
+        }
+
+        let offset = a.translation.truncate() - b.translation.truncate();
+        let distance = offset.length();
+        if distance >= PIG_RADIUS * 2.0 {
+            continue;
+        }
+
+        // Two pigs on exactly the same spot have no direction between them,
+        // which is exactly what happens when they are spawned, so we pick one
+        // at random.
+        let direction = offset
+            .try_normalize()
+            .unwrap_or_else(|| Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)));
+        let push = direction * (PIG_RADIUS * 2.0 - distance) / 2.0;
+        a.translation.x += push.x;
+        a.translation.y += push.y;
+        b.translation.x -= push.x;
+        b.translation.y -= push.y;
+    }
+}
+
+// Steps the transform towards the target, never overshooting it. Returns true
+// once the transform is within ARRIVAL_RADIUS of the target. 
+pub fn move_toward(transform: &mut Transform, target: Vec2, speed: f32, delta: f32) -> bool {
+    let position = transform.translation.truncate();
+    let to_target = target - position;
+    let distance = to_target.length();
+
+    if distance <= ARRIVAL_RADIUS {
+        return true;
+    }
+
+    let step = (speed * delta).min(distance);
+    let new_position = position + to_target / distance * step;
+    transform.translation.x = new_position.x;
+    transform.translation.y = new_position.y;
+
+    distance - step <= ARRIVAL_RADIUS
+}
+
+// If we can't afford a pig and there are none left to sell then we have no way
+// of ever making money again, so the game is over. 
+fn check_game_over(
+    money: Res<Money>,
+    price: Res<PigPrice>,
+    pigs: Query<(), With<Pig>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if money.0 < price.0 && pigs.is_empty() {
+        info!("Out of money and out of pigs, game over");
+        next_state.set(GameState::GameOver);
+    }
+}
+
+// Keeping lots of pigs around costs money. Once there are more than threshold
+// pigs, every pig over the limit drains rate_per_excess money each second, so
+// there is a reason to sell rather than hoarding pigs forever. 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Upkeep {
+    pub threshold: u32,
+    pub rate_per_excess: f32,
+}
+
+impl Default for Upkeep {
+    fn default() -> Self {
+        Upkeep {
+            threshold: 50,
+            rate_per_excess: 0.5,
         }
     }
 }
 
+impl Upkeep {
+    // How much money per second the given number of pigs is costing us.
+    pub fn cost_per_second(&self, pig_count: usize) -> f32 {
+        let excess = (pig_count as u32).saturating_sub(self.threshold);
+        excess as f32 * self.rate_per_excess
+    }
+}
+
+fn pig_upkeep(
+    upkeep: Res<Upkeep>,
+    pigs: Query<(), With<Pig>>,
+    mut money: ResMut<Money>,
+    time: Res<Time<Fixed>>,
+) {
+    let cost = upkeep.cost_per_second(pigs.iter().count()) * time.delta_seconds();
+    if cost > 0.0 {
+        // Upkeep can take us down to nothing but never into debt.
+        money.0 = (money.0 - cost).max(0.0);
+    }
+}
+
 // Bevy allows for parent child hierarchies which means we can move things
 // around as groups based on their parent or move them relative to their
 // parent depending on if we use Transform or GlobalTransform. 
@@ -176,4 +1028,195 @@ fn spawn_pig_parent(mut commands: Commands) {
         // even when using empty parent structs. 
         (SpatialBundle::default(), PigParent, Name::new("Pig parent"),)
     );
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    // An app with just the economy in it, running at a fixed frame rate, and
+    // an empty pig parent ready for the test to put pigs under. 
+    fn economy_app(fps: f64) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            // Every update pretends exactly one frame's worth of time has
+            // passed, so the test doesn't depend on how fast it runs. 
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps)))
+            .add_state::<GameState>()
+            .init_resource::<Money>()
+            .init_resource::<Stats>()
+            .init_resource::<Currency>()
+            .init_resource::<GameConfig>()
+            .init_resource::<SalesTax>()
+            .init_resource::<TaxCollected>()
+            .init_resource::<PayoutLimits>()
+            .add_event::<PigSoldEvent>()
+            .add_systems(FixedUpdate, pig_lifetime)
+            .add_systems(Update, on_pig_sold);
+        app.world.spawn((SpatialBundle::default(), PigParent));
+        app
+    }
+
+    fn add_pig(app: &mut App, pig: Pig) -> Entity {
+        let parent = app
+            .world
+            .query_filtered::<Entity, With<PigParent>>()
+            .single(&app.world);
+        let pig = app.world.spawn((SpatialBundle::default(), pig)).id();
+        app.world.entity_mut(parent).add_child(pig);
+        pig
+    }
+
+    // Returns how many seconds of real time pass before a pig with the given
+    // lifetime is sold. 
+    fn seconds_until_sold(fps: f64, lifetime: f32) -> f64 {
+        let mut app = economy_app(fps);
+        add_pig(
+            &mut app,
+            Pig {
+                lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+                max_value: 50.0,
+                ..default()
+            },
+        );
+        let starting_money = app.world.resource::<Money>().0;
+
+        while app.world.resource::<Money>().0 == starting_money {
+            app.update();
+            assert!(app.world.resource::<Time<Real>>().elapsed_seconds() < lifetime * 2.0);
+        }
+        app.world.resource::<Time<Real>>().elapsed_seconds_f64()
+    }
+
+    #[test]
+    fn pigs_sell_at_the_same_rate_at_any_frame_rate() {
+        // Pigs age in fixed steps, so a pig can be sold up to one step after
+        // its lifetime runs out, and then only on the next frame. The very
+        // first frame doesn't count as any time passing, which can add one
+        // more frame on top. 
+        let step = Time::<Fixed>::default().timestep().as_secs_f64();
+        for lifetime in [1.0, 5.0, 7.3] {
+            for fps in [30.0, 144.0] {
+                let late_by = seconds_until_sold(fps, lifetime) - lifetime as f64;
+                assert!(late_by >= 0.0);
+                assert!(late_by <= step + 2.0 / fps);
+            }
+        }
+    }
+
+    #[test]
+    fn payout_limits_bound_extreme_payouts() {
+        // A huge refund pushes the payout way over the ceiling, a huge tax
+        // pushes it way under the floor. 
+        for tax_percent in [-1_000_000.0, 1_000_000.0] {
+            let mut app = economy_app(60.0);
+            app.insert_resource(PayoutLimits {
+                floor: 5.0,
+                ceiling: 500.0,
+            })
+            .insert_resource(SalesTax {
+                percent: tax_percent,
+            });
+
+            // Golden pigs worth a fortune. The first lives long enough that we
+            // can check its value as it ages, the second sells straight away.
+            let ageing = add_pig(
+                &mut app,
+                Pig {
+                    lifetime: Timer::from_seconds(1000.0, TimerMode::Once),
+                    max_value: 1e30,
+                    breed: PigBreed::Golden,
+                    ..default()
+                },
+            );
+            add_pig(
+                &mut app,
+                Pig {
+                    lifetime: Timer::from_seconds(0.001, TimerMode::Once),
+                    max_value: 1e30,
+                    breed: PigBreed::Golden,
+                    ..default()
+                },
+            );
+
+            let mut sold = app.world.resource::<Events<PigSoldEvent>>().get_reader();
+            let mut payouts = Vec::new();
+            for _ in 0..10 {
+                app.update();
+                let events = app.world.resource::<Events<PigSoldEvent>>();
+                payouts.extend(sold.read(events).map(|event| event.value));
+
+                // The pig's value is only worked out once it has started to
+                // age, before then it's whatever we gave it above. 
+                let pig = app.world.get::<Pig>(ageing).unwrap();
+                if pig.lifetime.elapsed_secs() > 0.0 {
+                    assert!((5.0..=500.0).contains(&pig.value));
+                }
+            }
+
+            assert!(app.world.get::<Pig>(ageing).unwrap().lifetime.elapsed_secs() > 0.0);
+            assert_eq!(payouts.len(), 1);
+            assert!((5.0..=500.0).contains(&payouts[0]));
+        }
+    }
+
+    #[test]
+    fn payout_limits_floor_above_ceiling_does_not_panic() {
+        let limits = PayoutLimits {
+            floor: 100.0,
+            ceiling: 10.0,
+        };
+
+        // The ceiling is applied last so it wins. 
+        for payout in [-1e30, 0.0, 50.0, 1e30] {
+            assert_eq!(limits.apply(payout), limits.ceiling);
+        }
+    }
+
+    #[test]
+    fn move_toward_stops_within_arrival_radius() {
+        let target = Vec2::new(100.0, 50.0);
+        let mut transform = Transform::default();
+
+        // Ten seconds of frames at 60fps is plenty to walk this far. 
+        let mut arrived = false;
+        for _ in 0..600 {
+            arrived = move_toward(&mut transform, target, PIG_WALK_SPEED, 1.0 / 60.0);
+            if arrived {
+                break;
+            }
+        }
+
+        assert!(arrived);
+        assert!(transform.translation.truncate().distance(target) <= ARRIVAL_RADIUS);
+    }
+
+    #[test]
+    fn move_toward_never_overshoots() {
+        let target = Vec2::new(100.0, 0.0);
+        let mut transform = Transform::default();
+
+        // Each step is 30 pixels, which doesn't divide evenly into 100, so the
+        // last step has to be cut short. 
+        while !move_toward(&mut transform, target, 60.0, 0.5) {
+            assert!(transform.translation.x <= target.x);
+        }
+        assert!(transform.translation.x <= target.x);
+
+        // A single step far longer than the distance lands on the target
+        // rather than flying past it. 
+        let mut transform = Transform::default();
+        assert!(move_toward(&mut transform, target, 10_000.0, 1.0));
+        assert!(transform.translation.truncate().distance(target) < 0.001);
+    }
+
+    #[test]
+    fn move_toward_does_not_move_once_arrived() {
+        let target = Vec2::new(1.0, 1.0);
+        let mut transform = Transform::from_xyz(0.0, 0.0, 3.0);
+
+        assert!(move_toward(&mut transform, target, PIG_WALK_SPEED, 1.0));
+        assert_eq!(transform.translation, Vec3::new(0.0, 0.0, 3.0));
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::pigs::{BuyPig, Pig, PigParent};
+use crate::{GameState, Money};
+
+// Saving and loading the game so progress isn't lost when it's closed. F5
+// saves, F9 loads, and any existing save is loaded when the game starts.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_on_startup)
+            .add_systems(Update, save_game)
+            // Loading clears out the pigs we have now before the saved herd is
+            // bought back. Running in PreUpdate means those despawns have been
+            // applied by the time spawn_pig runs in Update, so the old pigs
+            // don't count towards the pig limit or the price. It has to come
+            // after InputSystem or it would see last frame's key presses.
+            // spawn_pig only runs while playing, so loading does too, or the
+            // saved herd would be dropped while the game is paused.
+            .add_systems(
+                PreUpdate,
+                load_game
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+const SAVE_PATH: &str = "save.ron";
+
+// Everything that goes into the save file. Serialize and Deserialize come from
+// serde and let ron turn this struct into text and back again.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub money: f32,
+    pub pig_count: usize,
+}
+
+fn save_game(input: Res<Input<KeyCode>>, money: Res<Money>, pigs: Query<(), With<Pig>>) {
+    if !input.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    let data = SaveData {
+        money: money.0,
+        pig_count: pigs.iter().count(),
+    };
+    let text = match ron::ser::to_string_pretty(&data, ron::ser::PrettyConfig::default()) {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Couldn't save the game: {error}");
+            return;
+        }
+    };
+
+    match std::fs::write(SAVE_PATH, text) {
+        Ok(()) => info!("Saved the game to {SAVE_PATH}"),
+        Err(error) => warn!("Couldn't write {SAVE_PATH}: {error}"),
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut money: ResMut<Money>,
+    parent: Query<Entity, With<PigParent>>,
+    mut buy: EventWriter<BuyPig>,
+) {
+    if !input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let Some(data) = read_save() else {
+        return;
+    };
+
+    // The same as resetting the game, every pig goes but the parent stays.
+    for parent in &parent {
+        commands.entity(parent).despawn_descendants();
+    }
+    apply_save(data, &mut money, &mut buy);
+}
+
+// There are no pigs yet when the game starts, so there's nothing to clear.
+fn load_on_startup(mut money: ResMut<Money>, mut buy: EventWriter<BuyPig>) {
+    if let Some(data) = read_save() {
+        apply_save(data, &mut money, &mut buy);
+    }
+}
+
+// A missing save just means we're starting fresh. A save we can't read gets a
+// warning, and either way we carry on with whatever we already have rather
+// than crashing.
+fn read_save() -> Option<SaveData> {
+    let text = match std::fs::read_to_string(SAVE_PATH) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            warn!("Couldn't read {SAVE_PATH}: {error}");
+            return None;
+        }
+    };
+
+    match ron::from_str::<SaveData>(&text) {
+        Ok(data) => Some(data),
+        Err(error) => {
+            warn!("{SAVE_PATH} is corrupt, ignoring it: {error}");
+            None
+        }
+    }
+}
+
+// The saved pigs come back through spawn_pig like any other pig, just without
+// charging us for them, so they follow the same rules as the pigs we buy.
+fn apply_save(data: SaveData, money: &mut Money, buy: &mut EventWriter<BuyPig>) {
+    money.0 = data.money;
+    if data.pig_count > 0 {
+        buy.send(BuyPig {
+            count: data.pig_count,
+            free: true,
+        });
+    }
+    info!("Loaded the game with {} pigs", data.pig_count);
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+
+// Takes a picture of the game window and saves it as a png.
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenshotSettings>()
+            .add_systems(Update, take_screenshot);
+    }
+}
+
+#[derive(Resource)]
+pub struct ScreenshotSettings {
+    pub key: KeyCode,
+    // The folder screenshots are saved into, it is created if it's missing.
+    pub directory: PathBuf,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        ScreenshotSettings {
+            key: KeyCode::F12,
+            directory: PathBuf::from("screenshots"),
+        }
+    }
+}
+
+fn take_screenshot(
+    input: Res<Input<KeyCode>>,
+    settings: Res<ScreenshotSettings>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+) {
+    if !input.just_pressed(settings.key) {
+        return;
+    }
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    if let Err(error) = std::fs::create_dir_all(&settings.directory) {
+        warn!("Couldn't create {}: {error}", settings.directory.display());
+        return;
+    }
+
+    // Naming the file after the current time means screenshots never
+    // overwrite each other.
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or_default();
+    let path = settings.directory.join(format!("screenshot-{millis}.png"));
+
+    // The picture isn't ready until the frame has been drawn, so bevy hands it
+    // to this closure later on a background thread. Anything going wrong in
+    // there is only worth a warning, it's not a reason to crash the game.
+    let result = screenshots.take_screenshot(window, move |image| {
+        let image = match image.try_into_dynamic() {
+            Ok(image) => image,
+            Err(error) => {
+                warn!("Couldn't convert the screenshot: {error}");
+                return;
+            }
+        };
+        match image.to_rgb8().save(&path) {
+            Ok(()) => info!("Saved screenshot to {}", path.display()),
+            Err(error) => warn!("Couldn't save screenshot to {}: {error}", path.display()),
+        }
+    });
+
+    if result.is_err() {
+        warn!("Already taking a screenshot, try again in a moment");
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use bevy::window::{CompositeAlphaMode, PrimaryWindow, WindowMode};
+
+// Settings that have to be known before the app is built, like how the window
+// is created. These can't be changed from inside the game since by the time a
+// system runs the window already exists, so they are read from environment
+// variables when the game launches instead.
+
+// Setting PIG_TRANSPARENT_WINDOW=1 makes the window background see-through so
+// only the sprites and UI are drawn over the desktop, handy for streaming.
+#[derive(Resource, Clone, Default)]
+pub struct WindowSettings {
+    pub transparent: bool,
+}
+
+impl WindowSettings {
+    pub fn from_env() -> Self {
+        WindowSettings {
+            transparent: env_flag("PIG_TRANSPARENT_WINDOW"),
+        }
+    }
+
+    // Not every platform can draw a transparent window. Where it isn't
+    // supported we quietly use the normal background and warn about it in
+    // setup, once logging is up and running.
+    pub fn transparency_supported() -> bool {
+        !cfg!(any(
+            target_os = "ios",
+            target_os = "android",
+            target_arch = "wasm32"
+        ))
+    }
+
+    pub fn use_transparency(&self) -> bool {
+        self.transparent && Self::transparency_supported()
+    }
+
+    // Applies these settings on top of the window we would otherwise create.
+    pub fn apply(&self, window: Window) -> Window {
+        if !self.use_transparency() {
+            return window;
+        }
+
+        Window {
+            transparent: true,
+            // macOS needs to be told how to blend the see-through parts of the
+            // window with whatever is behind it.
+            composite_alpha_mode: if cfg!(target_os = "macos") {
+                CompositeAlphaMode::PostMultiplied
+            } else {
+                window.composite_alpha_mode
+            },
+            ..window
+        }
+    }
+
+    // The colour the camera clears the screen to each frame. For a transparent
+    // window that needs to be fully see-through.
+    pub fn clear_color(&self) -> Color {
+        if self.use_transparency() {
+            Color::NONE
+        } else {
+            Color::PURPLE
+        }
+    }
+}
+
+// By default every texture uses nearest filtering so our pixel art stays crisp.
+// Setting PIG_LINEAR_FILTERING=1 switches to smooth linear filtering instead,
+// which looks better for assets that aren't pixel art. The sampler is picked
+// when the ImagePlugin is built, so changing this needs a restart.
+#[derive(Clone, Default)]
+pub struct ImageSettings {
+    pub linear_filtering: bool,
+}
+
+impl ImageSettings {
+    pub fn from_env() -> Self {
+        ImageSettings {
+            linear_filtering: env_flag("PIG_LINEAR_FILTERING"),
+        }
+    }
+
+    pub fn image_plugin(&self) -> ImagePlugin {
+        if self.linear_filtering {
+            ImagePlugin::default_linear()
+        } else {
+            ImagePlugin::default_nearest()
+        }
+    }
+}
+
+// The symbol shown in front of every amount of money, both on screen and in
+// the logs. It starts as £ unless PIG_CURRENCY is set, and C flips through the
+// built in symbols while playing.
+#[derive(Resource, Clone)]
+pub struct Currency {
+    pub symbol: String,
+}
+
+const CURRENCY_SYMBOLS: [&str; 3] = ["£", "$", "€"];
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency {
+            symbol: std::env::var("PIG_CURRENCY").unwrap_or_else(|_| CURRENCY_SYMBOLS[0].into()),
+        }
+    }
+}
+
+impl Currency {
+    // Every bit of code that shows money goes through here so they all agree.
+    // Pig values grow a tiny bit every frame, so we always round to pennies
+    // rather than showing every digit of the float.
+    pub fn format(&self, amount: f32) -> String {
+        format!("{}{:.2}", self.symbol, amount)
+    }
+
+    // Moves on to the next built in symbol. A custom symbol from PIG_CURRENCY
+    // isn't in the list so it goes back to the first one.
+    pub fn cycle(&mut self) {
+        let next = CURRENCY_SYMBOLS
+            .iter()
+            .position(|symbol| *symbol == self.symbol)
+            .map_or(0, |index| (index + 1) % CURRENCY_SYMBOLS.len());
+        self.symbol = CURRENCY_SYMBOLS[next].into();
+    }
+}
+
+pub fn cycle_currency(input: Res<Input<KeyCode>>, mut currency: ResMut<Currency>) {
+    if input.just_pressed(KeyCode::C) {
+        currency.cycle();
+        info!("Now showing money in {}", currency.symbol);
+    }
+}
+
+// F11 switches between a normal window and borderless fullscreen. Borderless
+// keeps the desktop's resolution so switching is instant. Everything that
+// cares about the window's size reads it fresh each frame, so the camera and
+// UI just follow along.
+pub fn toggle_fullscreen(
+    input: Res<Input<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !input.just_pressed(KeyCode::F11) {
+        return;
+    }
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
+
+// Which keys do what. Systems read their keys from here instead of naming a
+// KeyCode themselves, so changing a key only has to happen in one place.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub spawn_pig: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: KeyCode::W,
+            down: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            spawn_pig: KeyCode::Space,
+        }
+    }
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::pigs::{move_toward, MoveTarget, Pig};
+use crate::{GameState, Player};
+
+// Pressing X spooks every pig at once and they all run away from the player
+// for a few seconds. Good for clearing some space, or just for fun.
+pub struct StampedePlugin;
+
+impl Plugin for StampedePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StampedeSettings>()
+            .init_resource::<StampedeTimer>()
+            .register_type::<StampedeSettings>()
+            .add_systems(
+                Update,
+                (start_stampede, stampede)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct StampedeSettings {
+    // How fast the pigs run while stampeding, they normally walk at 60.
+    pub speed: f32,
+    // How many seconds the stampede lasts.
+    pub duration: f32,
+}
+
+impl Default for StampedeSettings {
+    fn default() -> Self {
+        StampedeSettings {
+            speed: 200.0,
+            duration: 3.0,
+        }
+    }
+}
+
+// Counts down while a stampede is happening, None the rest of the time.
+#[derive(Resource, Default)]
+pub struct StampedeTimer(pub Option<Timer>);
+
+// How far ahead of itself each pig aims as it runs away. The exact number
+// doesn't matter much as the target is moved every frame.
+const FLEE_DISTANCE: f32 = 100.0;
+
+// Half the size of the pig sprite, used to stop pigs running off the screen.
+const PIG_HALF_SIZE: Vec2 = Vec2::new(16.0, 8.0);
+
+fn start_stampede(
+    input: Res<Input<KeyCode>>,
+    settings: Res<StampedeSettings>,
+    mut timer: ResMut<StampedeTimer>,
+) {
+    if input.just_pressed(KeyCode::X) {
+        // The duration is editable in the inspector, and a negative one would
+        // panic when the timer is made, so zero is as low as it goes.
+        timer.0 = Some(Timer::from_seconds(settings.duration.max(0.0), TimerMode::Once));
+    }
+}
+
+fn stampede(
+    mut timer: ResMut<StampedeTimer>,
+    settings: Res<StampedeSettings>,
+    player: Query<&Transform, (With<Player>, Without<Pig>)>,
+    mut pigs: Query<(&mut Transform, &mut MoveTarget), With<Pig>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+) {
+    let Some(stampede_timer) = timer.0.as_mut() else {
+        return;
+    };
+    if stampede_timer.tick(time.delta()).finished() {
+        // The pigs just stop where they are, they have already cleared their
+        // targets so nothing is pulling them anywhere.
+        timer.0 = None;
+        return;
+    }
+
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let player_position = player.translation.truncate();
+
+    // Pigs aren't allowed to run out of the window. The pig parent sits at the
+    // origin, which is the middle of the screen.
+    let bounds = windows
+        .get_single()
+        .map(|window| Vec2::new(window.width(), window.height()) / 2.0 - PIG_HALF_SIZE)
+        .unwrap_or(Vec2::splat(f32::INFINITY));
+
+    for (mut transform, mut target) in &mut pigs {
+        // While the stampede is on it takes over from whatever the pig was
+        // doing, otherwise move_to_target would pull it back the other way.
+        target.0 = None;
+
+        let position = transform.translation.truncate();
+        // A pig standing right on top of the player has no "away", so it just
+        // picks a direction.
+        let away = (position - player_position)
+            .try_normalize()
+            .unwrap_or(Vec2::X);
+        let flee_point = (position + away * FLEE_DISTANCE).clamp(-bounds, bounds);
+
+        move_toward(&mut transform, flee_point, settings.speed, time.delta_seconds());
+    }
+}
@@ -1,15 +1,115 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::prelude::*;
-use crate::Money;
+use crate::{GameState, Money, Stats};
+use crate::pigs::{AutoSellRule, BuyPig, CantAffordFeedback, CantAffordPig, Pig, PigPrice, Upkeep};
+use crate::camera::GameViewport;
+use crate::settings::Currency;
 pub struct GameUi;
 
+#[derive(Component)]
+pub struct UiRoot;
+
+// Settings for how the HUD looks. Opacity goes from 0 (invisible) to 1 (fully
+// solid) and is handy for making the HUD less in your face while streaming.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct UiSettings {
+    pub opacity: f32,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        UiSettings { opacity: 1.0 }
+    }
+}
+
 #[derive(Component)]
 pub struct MoneyText;
 
+#[derive(Component)]
+pub struct PigCountText;
+
+#[derive(Component)]
+pub struct TotalEarnedText;
+
+#[derive(Component)]
+pub struct PigPriceText;
+
+#[derive(Component)]
+pub struct AutoSellText;
+
+// Counts down while a piece of text is flashing red. When it runs out the text
+// is back to its normal colour. Living on the text itself means any text can be
+// made to flash, not just the money.
+#[derive(Component)]
+pub struct FlashTimer(pub Timer);
+
+impl Default for FlashTimer {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(0.5, TimerMode::Once);
+        // We don't want to flash as soon as the game starts, so the timer
+        // starts off already finished.
+        timer.tick(timer.duration());
+        FlashTimer(timer)
+    }
+}
+
+#[derive(Component)]
+pub struct UpkeepText;
+
+// Tag component for the game over message, so it can be cleared away again
+// when we restart.
+#[derive(Component)]
+pub struct GameOverScreen;
+
+// Tag component for the dimmed panel shown over the game while it's paused.
+#[derive(Component)]
+pub struct PauseOverlay;
+
+// Tag component for the frames per second readout, which is hidden until F3
+// is pressed.
+#[derive(Component)]
+pub struct FpsText;
+
+// Tag component for the button that buys a pig, so the game can be played
+// with just the mouse.
+#[derive(Component)]
+pub struct BuyPigButton;
+
+// The colours the buy button changes between so you can see it react.
+const BUTTON_NORMAL: Color = Color::rgb(0.15, 0.15, 0.15);
+const BUTTON_HOVERED: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_PRESSED: Color = Color::rgb(0.35, 0.75, 0.35);
+
 
 impl Plugin for GameUi {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_game_ui)
-            .add_systems(Update, update_money_ui);
+        app.init_resource::<UiSettings>()
+            .register_type::<UiSettings>()
+            .add_systems(Startup, spawn_game_ui)
+            // OnEnter and OnExit run once, right as the game moves into or out
+            // of a state, which is just what we want for showing and hiding a
+            // screen. 
+            .add_systems(OnEnter(GameState::GameOver), spawn_game_over_screen)
+            .add_systems(OnExit(GameState::GameOver), despawn_game_over_screen)
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_overlay)
+            .add_systems(OnExit(GameState::Paused), despawn_pause_overlay)
+            .add_systems(
+                Update,
+                (
+                    update_money_ui,
+                    update_pig_count_ui,
+                    update_pig_price_ui,
+                    update_total_earned_ui,
+                    (start_money_flash, flash_text).chain(),
+                    update_auto_sell_ui,
+                    update_upkeep_ui,
+                    buy_pig_button,
+                    (toggle_fps_text, update_fps_text),
+                    fit_ui_to_viewport,
+                    (adjust_ui_opacity, apply_ui_opacity).chain(),
+                ),
+            );
     }
 }
 
@@ -30,6 +130,7 @@ fn spawn_game_ui(mut commands: Commands) {
                 ..default()
             },
             Name::new("UI Root"),
+            UiRoot,
         ))
         .with_children(|commands| {
             commands.spawn((
@@ -46,15 +147,471 @@ fn spawn_game_ui(mut commands: Commands) {
                 // This is a tag component all it does is make it easy to 
                 // query for specific stuff. 
                 MoneyText,
+                FlashTimer::default(),
+            ));
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "Pigs: 0",
+                        TextStyle {
+                            font_size: 32.0,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                PigCountText,
+            ));
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                PigPriceText,
+            ));
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                TotalEarnedText,
+            ));
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                AutoSellText,
+            ));
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::ORANGE_RED,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                UpkeepText,
+            ));
+            // A button is just a node that bevy keeps an Interaction on, so
+            // we can tell when the mouse is over it or clicking it. The text
+            // goes inside it as a child. 
+            commands
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            margin: UiRect::left(Val::Px(20.0)),
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                            ..default()
+                        },
+                        background_color: BUTTON_NORMAL.into(),
+                        ..default()
+                    },
+                    BuyPigButton,
+                    Name::new("Buy pig button"),
+                ))
+                .with_children(|commands| {
+                    commands.spawn(TextBundle::from_section(
+                        "Buy Pig",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ));
+                });
+            commands.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "FPS: --",
+                        TextStyle {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ),
+                    style: Style {
+                        // An auto margin soaks up all the spare room, which
+                        // pushes this over to the right hand side of the bar.
+                        margin: UiRect::left(Val::Auto),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                FpsText,
             ));
         });
 }
 
+// A full window node that centres its child, with the message inside it.
+fn spawn_game_over_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(50),
+                ..default()
+            },
+            GameOverScreen,
+            Name::new("Game over screen"),
+        ))
+        .with_children(|commands| {
+            commands.spawn(TextBundle::from_section(
+                "Game Over - press R to restart",
+                TextStyle {
+                    font_size: 40.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+// Much like the game over screen, but with a see-through black background so
+// the paused game is dimmed behind the text. The z index puts it above the HUD.
+fn spawn_pause_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(50),
+                ..default()
+            },
+            PauseOverlay,
+            Name::new("Pause overlay"),
+        ))
+        .with_children(|commands| {
+            commands.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn despawn_pause_overlay(mut commands: Commands, overlays: Query<Entity, With<PauseOverlay>>) {
+    for overlay in &overlays {
+        commands.entity(overlay).despawn_recursive();
+    }
+}
+
+fn despawn_game_over_screen(mut commands: Commands, screens: Query<Entity, With<GameOverScreen>>) {
+    for screen in &screens {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
 // This simply queries our money value. 
-fn update_money_ui(mut texts: Query<&mut Text, With<MoneyText>>, money: Res<Money>) {
+fn update_money_ui(
+    mut texts: Query<&mut Text, With<MoneyText>>,
+    money: Res<Money>,
+    currency: Res<Currency>,
+) {
     // This then iterates through the results and then formats that text into
     // a string that we use to update the text within our ui. 
     for mut text in &mut texts {
-        text.sections[0].value = format!("Money: £{:?}", money.0);
+        text.sections[0].value = format!("Money: {}", currency.format(money.0));
+    }
+}
+
+// Counts how many pigs we have right now. Query<(), With<Pig>> doesn't fetch
+// any data at all, it just matches every entity with a Pig component, which is
+// all we need for counting.
+fn update_pig_count_ui(
+    mut texts: Query<&mut Text, With<PigCountText>>,
+    pigs: Query<(), With<Pig>>,
+) {
+    let count = pigs.iter().count();
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Pigs: {count}");
     }
-}
\ No newline at end of file
+}
+
+// Shows what the next pig will cost us. This is checked every frame so the
+// text keeps up if the price changes while we play.
+fn update_pig_price_ui(
+    mut texts: Query<&mut Text, With<PigPriceText>>,
+    price: Res<PigPrice>,
+    currency: Res<Currency>,
+) {
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Pig price: {}", currency.format(price.0));
+    }
+}
+
+fn update_total_earned_ui(
+    mut texts: Query<&mut Text, With<TotalEarnedText>>,
+    stats: Res<Stats>,
+    currency: Res<Currency>,
+) {
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Total earned: {}", currency.format(stats.total_earned));
+    }
+}
+
+// When we try to buy a pig we can't afford, the money text goes red so it is
+// obvious why nothing happened.
+fn start_money_flash(
+    mut cant_afford: EventReader<CantAffordPig>,
+    feedback: Res<CantAffordFeedback>,
+    mut flashes: Query<&mut FlashTimer, With<MoneyText>>,
+) {
+    // read() drains the events so we don't see the same ones next frame. We
+    // only care whether there were any, not how many.
+    if cant_afford.read().count() > 0 && feedback.flash {
+        for mut flash in &mut flashes {
+            flash.0.reset();
+        }
+    }
+}
+
+// Fades any flashing text from red back to white over the length of its flash.
+fn flash_text(mut texts: Query<(&mut Text, &mut FlashTimer)>, time: Res<Time>) {
+    let red = Vec4::from(Color::RED.as_rgba_f32());
+    let white = Vec4::from(Color::WHITE.as_rgba_f32());
+
+    for (mut text, mut flash) in &mut texts {
+        if flash.0.finished() {
+            continue;
+        }
+        flash.0.tick(time.delta());
+
+        let [r, g, b, _] = red.lerp(white, flash.0.percent()).to_array();
+        for section in &mut text.sections {
+            // Keep whatever alpha the UI opacity setting gave the text.
+            let alpha = section.style.color.a();
+            section.style.color = Color::rgba(r, g, b, alpha);
+        }
+    }
+}
+
+// Shows the auto-sell rule while it is switched on, and nothing otherwise.
+fn update_auto_sell_ui(
+    mut texts: Query<&mut Text, With<AutoSellText>>,
+    rule: Res<AutoSellRule>,
+    currency: Res<Currency>,
+) {
+    for mut text in &mut texts {
+        text.sections[0].value = if rule.enabled {
+            format!("Auto-selling pigs worth over {}", currency.format(rule.min_value))
+        } else {
+            String::new()
+        };
+    }
+}
+
+// Shows how much the herd is costing us each second, but only once there are
+// enough pigs for upkeep to kick in.
+fn update_upkeep_ui(
+    mut texts: Query<&mut Text, With<UpkeepText>>,
+    upkeep: Res<Upkeep>,
+    pigs: Query<(), With<Pig>>,
+    currency: Res<Currency>,
+) {
+    let cost = upkeep.cost_per_second(pigs.iter().count());
+    for mut text in &mut texts {
+        text.sections[0].value = if cost > 0.0 {
+            format!("Upkeep: -{}/s", currency.format(cost))
+        } else {
+            String::new()
+        };
+    }
+}
+
+// F3 shows and hides the FPS readout.
+fn toggle_fps_text(
+    input: Res<Input<KeyCode>>,
+    mut texts: Query<&mut Visibility, With<FpsText>>,
+) {
+    if !input.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut visibility in &mut texts {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+// FrameTimeDiagnosticsPlugin measures how long each frame takes and keeps a
+// smoothed average, so the number doesn't flicker about every frame.
+fn update_fps_text(
+    diagnostics: Res<DiagnosticsStore>,
+    mut texts: Query<(&mut Text, &Visibility), With<FpsText>>,
+) {
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+
+    for (mut text, visibility) in &mut texts {
+        // No point updating text nobody can see.
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        text.sections[0].value = match fps {
+            Some(fps) => format!("FPS: {fps:.0}"),
+            None => "FPS: --".to_string(),
+        };
+    }
+}
+
+// The filters for the buy button query, pulled out so the query stays
+// readable.
+type BuyPigButtonChanged = (Changed<Interaction>, With<BuyPigButton>);
+
+// Changed<Interaction> means we only hear about the button when the mouse does
+// something to it, rather than every frame. Clicking orders a single pig no
+// matter what, spawn_pig decides whether we can actually afford it.
+fn buy_pig_button(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), BuyPigButtonChanged>,
+    mut buy: EventWriter<BuyPig>,
+    settings: Res<UiSettings>,
+) {
+    for (interaction, mut background) in &mut buttons {
+        let color = match interaction {
+            Interaction::Pressed => {
+                buy.send(BuyPig { count: 1, free: false });
+                BUTTON_PRESSED
+            }
+            Interaction::Hovered => BUTTON_HOVERED,
+            Interaction::None => BUTTON_NORMAL,
+        };
+        // The button fades along with the rest of the HUD. 
+        background.0 = color.with_a(settings.opacity);
+    }
+}
+
+// When the camera is letterboxing, the UI bar is moved so that it sits across
+// the top of the game area instead of the top of the window.
+fn fit_ui_to_viewport(
+    viewport: Res<GameViewport>,
+    mut roots: Query<&mut Style, With<UiRoot>>,
+) {
+    if !viewport.is_changed() {
+        return;
+    }
+
+    for mut style in &mut roots {
+        match viewport.0 {
+            Some(rect) => {
+                style.position_type = PositionType::Absolute;
+                style.left = Val::Px(rect.min.x);
+                style.top = Val::Px(rect.min.y);
+                style.width = Val::Px(rect.width());
+                style.height = Val::Px(rect.height() * 0.1);
+            }
+            None => {
+                style.position_type = PositionType::Relative;
+                style.left = Val::Auto;
+                style.top = Val::Auto;
+                style.width = Val::Percent(100.0);
+                style.height = Val::Percent(10.0);
+            }
+        }
+    }
+}
+
+// [ and ] fade the HUD out and back in a step at a time.
+fn adjust_ui_opacity(input: Res<Input<KeyCode>>, mut settings: ResMut<UiSettings>) {
+    if input.just_pressed(KeyCode::BracketLeft) {
+        settings.opacity -= 0.1;
+    }
+    if input.just_pressed(KeyCode::BracketRight) {
+        settings.opacity += 0.1;
+    }
+    // Only clamp when we're actually out of range, otherwise we'd mark the
+    // settings as changed every single frame.
+    if !(0.0..=1.0).contains(&settings.opacity) {
+        settings.opacity = settings.opacity.clamp(0.0, 1.0);
+    }
+}
+
+// This only changes the alpha of each colour and leaves the red, green and blue
+// alone, so anything else that recolours the UI still works alongside it. Only
+// the HUD bar and everything inside it is faded, the pause and game over
+// screens aren't part of the HUD and must stay readable. iter_descendants walks
+// down through the children, and their children, all the way to the bottom. 
+fn apply_ui_opacity(
+    settings: Res<UiSettings>,
+    roots: Query<Entity, With<UiRoot>>,
+    children: Query<&Children>,
+    mut backgrounds: Query<&mut BackgroundColor>,
+    mut texts: Query<&mut Text>,
+) {
+    let opacity = settings.opacity;
+
+    for root in &roots {
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            if let Ok(mut background) = backgrounds.get_mut(entity) {
+                if background.0.a() != opacity {
+                    background.0.set_a(opacity);
+                }
+            }
+            if let Ok(mut text) = texts.get_mut(entity) {
+                if text.sections.iter().any(|section| section.style.color.a() != opacity) {
+                    for section in &mut text.sections {
+                        section.style.color.set_a(opacity);
+                    }
+                }
+            }
+        }
+    }
+}